@@ -0,0 +1,106 @@
+// src/backend/dry_run.rs
+
+use super::GhBackend;
+use crate::config::NodeConfig;
+use crate::github::{Codespace, GHError};
+use chrono::Utc;
+use std::sync::Mutex;
+
+/// Bungkus backend lain: panggilan read-only (list, view, api) diteruskan,
+/// panggilan yang mengubah sesuatu hanya di-print sebagai command `gh`.
+/// Codespace yang "dibuat" atau "dihapus" dicatat supaya list/view konsisten.
+pub struct DryRunBackend {
+    inner: Box<dyn GhBackend>,
+    planned: Mutex<Planned>,
+}
+
+#[derive(Default)]
+struct Planned {
+    created: Vec<Codespace>,
+    deleted: Vec<String>,
+}
+
+impl DryRunBackend {
+    pub fn new(inner: Box<dyn GhBackend>) -> Self {
+        DryRunBackend { inner, planned: Mutex::new(Planned::default()) }
+    }
+
+    fn print(args: &[&str]) {
+        info!("[dry-run] gh {}", args.join(" "));
+    }
+}
+
+impl GhBackend for DryRunBackend {
+    fn list_codespaces(&self, token: &str, repo: &str) -> Result<Vec<Codespace>, GHError> {
+        let planned = self.planned.lock().unwrap();
+        let mut codespaces: Vec<Codespace> = self.inner.list_codespaces(token, repo)?
+            .into_iter()
+            .filter(|cs| !planned.deleted.contains(&cs.name))
+            .collect();
+        codespaces.extend(planned.created.iter().filter(|cs| cs.repository == repo).cloned());
+        Ok(codespaces)
+    }
+
+    fn create_codespace(&self, _token: &str, repo: &str, node: &NodeConfig) -> Result<String, GHError> {
+        let idle_timeout = format!("{}m", node.idle_timeout_minutes);
+        let mut args = vec!["codespace", "create", "-r", repo, "-m", &node.machine, "--display-name", node.display_name(), "--idle-timeout", &idle_timeout];
+        if let Some(path) = &node.devcontainer_path {
+            args.extend(["--devcontainer-path", path.as_str()]);
+        }
+        Self::print(&args);
+
+        let mut planned = self.planned.lock().unwrap();
+        let name = format!("{}-dry-run-{}", node.display_name(), planned.created.len() + 1);
+        planned.created.push(Codespace {
+            name: name.clone(),
+            display_name: node.display_name().to_string(),
+            state: "Available".to_string(),
+            machine: node.machine.clone(),
+            repository: repo.to_string(),
+            created_at: Some(Utc::now()),
+            last_used_at: None,
+            idle_timeout_minutes: Some(node.idle_timeout_minutes),
+        });
+        Ok(name)
+    }
+
+    fn start_codespace(&self, _token: &str, name: &str) -> Result<(), GHError> {
+        Self::print(&["api", "-X", "POST", &format!("/user/codespaces/{}/start", name)]);
+        Ok(())
+    }
+
+    fn stop_codespace(&self, _token: &str, name: &str) -> Result<(), GHError> {
+        Self::print(&["codespace", "stop", "-c", name]);
+        Ok(())
+    }
+
+    fn delete_codespace(&self, _token: &str, name: &str) -> Result<(), GHError> {
+        Self::print(&["codespace", "delete", "-c", name, "--force"]);
+        let mut planned = self.planned.lock().unwrap();
+        planned.created.retain(|cs| cs.name != name);
+        planned.deleted.push(name.to_string());
+        Ok(())
+    }
+
+    fn view_codespace(&self, token: &str, name: &str) -> Result<Codespace, GHError> {
+        {
+            let planned = self.planned.lock().unwrap();
+            if let Some(cs) = planned.created.iter().find(|cs| cs.name == name) {
+                return Ok(cs.clone());
+            }
+            if planned.deleted.iter().any(|n| n == name) {
+                return Err(GHError::NotFound(format!("codespace '{}' not found", name)));
+            }
+        }
+        self.inner.view_codespace(token, name)
+    }
+
+    fn ssh(&self, _token: &str, name: &str, cmd: &str) -> Result<String, GHError> {
+        Self::print(&["codespace", "ssh", "-c", name, "--", cmd]);
+        if cmd.contains("echo 'ready'") { Ok("ready".to_string()) } else { Ok(String::new()) }
+    }
+
+    fn api(&self, token: &str, endpoint: &str) -> Result<String, GHError> {
+        self.inner.api(token, endpoint)
+    }
+}
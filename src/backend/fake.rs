@@ -0,0 +1,145 @@
+// src/backend/fake.rs
+
+use super::GhBackend;
+use crate::config::NodeConfig;
+use crate::github::{Codespace, GHError};
+use chrono::Utc;
+use std::sync::Mutex;
+
+/// Backend in-memory untuk menjalankan orchestrator tanpa akun GitHub.
+/// Login diambil dari 6 karakter terakhir token. Token yang mengandung
+/// "invalid" ditolak (401), token yang mengandung "exhausted" sudah memakai
+/// 120 core-hours bulan ini.
+#[derive(Default)]
+pub struct FakeBackend {
+    inner: Mutex<FakeState>,
+}
+
+#[derive(Default)]
+struct FakeState {
+    codespaces: Vec<Codespace>,
+    next_id: u32,
+}
+
+impl FakeBackend {
+    fn login_for(token: &str) -> String {
+        let tail: String = token.chars().rev().take(6).collect::<Vec<_>>().into_iter().rev().collect();
+        format!("fake-{}", tail)
+    }
+
+    fn not_found(name: &str) -> GHError {
+        GHError::NotFound(format!("codespace '{}' not found", name))
+    }
+
+    /// Paksa state codespace, untuk test state transisi (Starting, Failed, ...).
+    #[cfg(test)]
+    pub fn set_state(&self, name: &str, state: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(cs) = inner.codespaces.iter_mut().find(|cs| cs.name == name) {
+            cs.state = state.to_string();
+        }
+    }
+}
+
+impl GhBackend for FakeBackend {
+    fn list_codespaces(&self, _token: &str, repo: &str) -> Result<Vec<Codespace>, GHError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner.codespaces.iter().filter(|cs| cs.repository == repo).cloned().collect())
+    }
+
+    fn create_codespace(&self, _token: &str, repo: &str, node: &NodeConfig) -> Result<String, GHError> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let name = format!("{}-{:05}", node.display_name(), inner.next_id);
+        let now = Utc::now();
+        inner.codespaces.push(Codespace {
+            name: name.clone(),
+            display_name: node.display_name().to_string(),
+            state: "Available".to_string(),
+            machine: node.machine.clone(),
+            repository: repo.to_string(),
+            created_at: Some(now),
+            last_used_at: Some(now),
+            idle_timeout_minutes: Some(node.idle_timeout_minutes),
+        });
+        Ok(name)
+    }
+
+    fn start_codespace(&self, _token: &str, name: &str) -> Result<(), GHError> {
+        let mut inner = self.inner.lock().unwrap();
+        let cs = inner.codespaces.iter_mut().find(|cs| cs.name == name).ok_or_else(|| Self::not_found(name))?;
+        // Seperti GitHub: start ditolak selama shutdown belum selesai.
+        if cs.state == "ShuttingDown" {
+            cs.state = "Shutdown".to_string();
+            return Err(GHError::Http { status: 409, message: format!("codespace '{}' is shutting down", name) });
+        }
+        cs.state = "Available".to_string();
+        Ok(())
+    }
+
+    fn stop_codespace(&self, _token: &str, name: &str) -> Result<(), GHError> {
+        let mut inner = self.inner.lock().unwrap();
+        let cs = inner.codespaces.iter_mut().find(|cs| cs.name == name).ok_or_else(|| Self::not_found(name))?;
+        cs.state = "Shutdown".to_string();
+        Ok(())
+    }
+
+    fn delete_codespace(&self, _token: &str, name: &str) -> Result<(), GHError> {
+        let mut inner = self.inner.lock().unwrap();
+        let before = inner.codespaces.len();
+        inner.codespaces.retain(|cs| cs.name != name);
+        if inner.codespaces.len() == before {
+            return Err(Self::not_found(name));
+        }
+        Ok(())
+    }
+
+    fn view_codespace(&self, _token: &str, name: &str) -> Result<Codespace, GHError> {
+        let inner = self.inner.lock().unwrap();
+        inner.codespaces.iter()
+            .find(|cs| cs.name == name)
+            .cloned()
+            .ok_or_else(|| Self::not_found(name))
+    }
+
+    fn ssh(&self, _token: &str, name: &str, cmd: &str) -> Result<String, GHError> {
+        let inner = self.inner.lock().unwrap();
+        match inner.codespaces.iter().find(|cs| cs.name == name) {
+            Some(cs) if cs.is_available() => {
+                if cmd.contains("echo 'ready'") { Ok("ready".to_string()) } else { Ok(format!("[fake] {}", cmd)) }
+            }
+            Some(_) => Err(GHError::CommandError(format!("codespace '{}' is not running", name))),
+            None => Err(Self::not_found(name)),
+        }
+    }
+
+    fn api(&self, token: &str, endpoint: &str) -> Result<String, GHError> {
+        let endpoint = endpoint.trim_start_matches('/');
+        let endpoint = endpoint.split('?').next().unwrap_or(endpoint);
+        if endpoint == "user" {
+            if token.contains("invalid") {
+                return Err(GHError::AuthError("HTTP 401: Bad credentials".to_string()));
+            }
+            return Ok(serde_json::json!({ "login": Self::login_for(token) }).to_string());
+        }
+        if let Some(repo) = endpoint.strip_prefix("repos/").and_then(|r| r.strip_suffix("/codespaces/new")) {
+            // Owner berawalan "org-" dianggap organization yang membayar codespace.
+            let owner = repo.split('/').next().unwrap_or(repo);
+            let billable = if owner.starts_with("org-") {
+                serde_json::json!({ "login": owner, "type": "Organization" })
+            } else {
+                serde_json::json!({ "login": Self::login_for(token), "type": "User" })
+            };
+            return Ok(serde_json::json!({ "billable_owner": billable }).to_string());
+        }
+        if endpoint.ends_with("/settings/billing/usage") {
+            let items = if token.contains("exhausted") {
+                serde_json::json!([{ "product": "codespaces", "sku": "Compute 2-core", "quantity": 60, "unitType": "hours" }])
+            } else {
+                serde_json::json!([])
+            };
+            return Ok(serde_json::json!({ "usageItems": items }).to_string());
+        }
+        Err(GHError::NotFound(endpoint.to_string()))
+    }
+}
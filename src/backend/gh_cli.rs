@@ -0,0 +1,162 @@
+// src/backend/gh_cli.rs
+
+use super::{GhBackend, Timeouts};
+use crate::config::NodeConfig;
+use crate::github::{Codespace, GHError};
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Backend yang menjalankan `gh` CLI sebagai subprocess. Subprocess yang
+/// lewat batas `timeouts` di-kill.
+#[derive(Default)]
+pub struct GhCliBackend {
+    pub timeouts: Timeouts,
+}
+
+const LIST_FIELDS: &str = "name,displayName,state,machineName,repository,createdAt,lastUsedAt";
+const VIEW_FIELDS: &str = "name,displayName,state,machineName,repository,createdAt,lastUsedAt,idleTimeoutMinutes";
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
+/// Kill `gh` beserta turunannya (mis. `ssh` dari `gh codespace ssh`).
+/// Di unix `gh` jalan di process group sendiri, jadi seluruh group yang di-kill.
+fn kill_tree(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        let _ = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", child.id())])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn run_gh_command(token: &str, args: &[&str], timeout_secs: f64) -> Result<String, GHError> {
+    let timeout = Duration::from_secs_f64(timeout_secs);
+    debug!("gh {}", args.join(" "));
+    let mut command = Command::new("gh");
+    command
+        .args(args)
+        .env("GH_TOKEN", token)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| GHError::CommandError(format!("Failed to execute gh: {}", e)))?;
+
+    // Baca pipe di thread sendiri supaya child tidak macet saat buffer penuh.
+    let stdout_reader = read_pipe(child.stdout.take());
+    let stderr_reader = read_pipe(child.stderr.take());
+
+    let start = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if start.elapsed() >= timeout => {
+                kill_tree(&mut child);
+                return Err(GHError::Timeout {
+                    operation: format!("gh {}", args.iter().take_while(|a| !a.starts_with('-')).take(2).copied().collect::<Vec<_>>().join(" ")),
+                    after: timeout,
+                });
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(100)),
+            Err(e) => return Err(GHError::CommandError(format!("Failed to wait for gh: {}", e))),
+        }
+    };
+
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let stderr = stderr_reader.join().unwrap_or_default();
+    
+    if !status.success() {
+        if stderr.contains("no codespaces found") {
+            return Ok("".to_string());
+        }
+        return Err(classify_failure(stderr));
+    }
+    
+    Ok(stdout.trim().to_string())
+}
+
+/// Terjemahkan stderr `gh` yang gagal ke varian `GHError`.
+fn classify_failure(stderr: String) -> GHError {
+    let lower = stderr.to_lowercase();
+    if lower.contains("bad credentials") || lower.contains("authentication required") {
+        return GHError::AuthError(stderr);
+    }
+    match GHError::from_http(http_status(&stderr).unwrap_or(0), stderr, None) {
+        GHError::Http { status: 0, message } if message.to_lowercase().contains("not found") => GHError::NotFound(message),
+        GHError::Http { status: 0, message } => GHError::CommandError(message),
+        e => e,
+    }
+}
+
+/// Status dari pesan `gh` seperti "HTTP 404: Not Found (...)".
+fn http_status(stderr: &str) -> Option<u16> {
+    stderr.match_indices("HTTP ").find_map(|(i, _)| stderr.get(i + 5..i + 8)?.parse().ok())
+}
+
+impl GhBackend for GhCliBackend {
+    fn list_codespaces(&self, token: &str, repo: &str) -> Result<Vec<Codespace>, GHError> {
+        let output = run_gh_command(token, &["codespace", "list", "-r", repo, "--json", LIST_FIELDS], self.timeouts.list_secs)?;
+        if output.is_empty() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_str(&output)
+            .map_err(|e| GHError::Parse(format!("Failed to parse codespace list: {}", e)))
+    }
+
+    fn create_codespace(&self, token: &str, repo: &str, node: &NodeConfig) -> Result<String, GHError> {
+        let idle_timeout = format!("{}m", node.idle_timeout_minutes);
+        let mut args = vec!["codespace", "create", "-r", repo, "-m", &node.machine, "--display-name", node.display_name(), "--idle-timeout", &idle_timeout];
+        if let Some(path) = &node.devcontainer_path {
+            args.extend(["--devcontainer-path", path.as_str()]);
+        }
+        run_gh_command(token, &args, self.timeouts.create_secs)
+    }
+
+    fn start_codespace(&self, token: &str, name: &str) -> Result<(), GHError> {
+        // `gh codespace` tidak punya subcommand start, pakai REST lewat `gh api`.
+        let endpoint = format!("/user/codespaces/{}/start", name);
+        run_gh_command(token, &["api", "-X", "POST", &endpoint], self.timeouts.start_secs).map(|_| ())
+    }
+
+    fn stop_codespace(&self, token: &str, name: &str) -> Result<(), GHError> {
+        run_gh_command(token, &["codespace", "stop", "-c", name], self.timeouts.stop_secs).map(|_| ())
+    }
+
+    fn delete_codespace(&self, token: &str, name: &str) -> Result<(), GHError> {
+        run_gh_command(token, &["codespace", "delete", "-c", name, "--force"], self.timeouts.delete_secs).map(|_| ())
+    }
+
+    fn view_codespace(&self, token: &str, name: &str) -> Result<Codespace, GHError> {
+        let output = run_gh_command(token, &["codespace", "view", "-c", name, "--json", VIEW_FIELDS], self.timeouts.view_secs)?;
+        serde_json::from_str(&output)
+            .map_err(|e| GHError::Parse(format!("Failed to parse codespace '{}': {}", name, e)))
+    }
+
+    fn ssh(&self, token: &str, name: &str, cmd: &str) -> Result<String, GHError> {
+        run_gh_command(token, &["codespace", "ssh", "-c", name, "--", cmd], self.timeouts.ssh_secs)
+    }
+
+    fn api(&self, token: &str, endpoint: &str) -> Result<String, GHError> {
+        run_gh_command(token, &["api", endpoint, "-H", "Accept: application/vnd.github+json"], self.timeouts.api_secs)
+    }
+}
@@ -0,0 +1,106 @@
+// src/backend/mod.rs
+
+mod dry_run;
+mod fake;
+mod gh_cli;
+mod rest;
+
+pub use dry_run::DryRunBackend;
+pub use fake::FakeBackend;
+pub use gh_cli::GhCliBackend;
+pub use rest::{RestBackend, DEFAULT_API_URL};
+
+use crate::config::NodeConfig;
+use crate::github::{Codespace, GHError};
+use serde::Deserialize;
+use std::env;
+
+/// Timeout maksimal per operasi (satu hari), supaya konversi ke `Duration` tidak overflow.
+const MAX_TIMEOUT_SECS: f64 = 24.0 * 3600.0;
+
+/// Batas waktu per jenis operasi dalam detik, object `timeouts` di config.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct Timeouts {
+    pub list_secs: f64,
+    pub view_secs: f64,
+    pub api_secs: f64,
+    /// `gh codespace create` baru selesai setelah codespace dibuat.
+    pub create_secs: f64,
+    pub start_secs: f64,
+    pub stop_secs: f64,
+    pub delete_secs: f64,
+    /// Termasuk startup command yang dijalankan lewat SSH.
+    pub ssh_secs: f64,
+    /// Satu request backend REST.
+    pub request_secs: f64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            list_secs: 60.0,
+            view_secs: 60.0,
+            api_secs: 60.0,
+            create_secs: 10.0 * 60.0,
+            start_secs: 2.0 * 60.0,
+            stop_secs: 2.0 * 60.0,
+            delete_secs: 2.0 * 60.0,
+            ssh_secs: 5.0 * 60.0,
+            request_secs: 60.0,
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [
+            ("list_secs", self.list_secs),
+            ("view_secs", self.view_secs),
+            ("api_secs", self.api_secs),
+            ("create_secs", self.create_secs),
+            ("start_secs", self.start_secs),
+            ("stop_secs", self.stop_secs),
+            ("delete_secs", self.delete_secs),
+            ("ssh_secs", self.ssh_secs),
+            ("request_secs", self.request_secs),
+        ];
+        for (name, value) in values {
+            if !value.is_finite() || value <= 0.0 || value > MAX_TIMEOUT_SECS {
+                return Err(format!("timeouts.{} harus angka > 0 dan <= {}", name, MAX_TIMEOUT_SECS));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Semua operasi GitHub yang dipakai orchestrator. Implementasi default
+/// memanggil REST API langsung, `GhCliBackend` memanggil `gh` CLI,
+/// `FakeBackend` menyimpan semuanya di memory, `DryRunBackend` hanya
+/// print command yang mengubah sesuatu.
+pub trait GhBackend {
+    fn list_codespaces(&self, token: &str, repo: &str) -> Result<Vec<Codespace>, GHError>;
+    /// Return nama codespace yang baru dibuat.
+    fn create_codespace(&self, token: &str, repo: &str, node: &NodeConfig) -> Result<String, GHError>;
+    fn start_codespace(&self, token: &str, name: &str) -> Result<(), GHError>;
+    fn stop_codespace(&self, token: &str, name: &str) -> Result<(), GHError>;
+    fn delete_codespace(&self, token: &str, name: &str) -> Result<(), GHError>;
+    fn view_codespace(&self, token: &str, name: &str) -> Result<Codespace, GHError>;
+    fn ssh(&self, token: &str, name: &str, cmd: &str) -> Result<String, GHError>;
+    /// GET ke REST endpoint, return body JSON.
+    fn api(&self, token: &str, endpoint: &str) -> Result<String, GHError>;
+}
+
+/// Pilih backend dari env `ORCHESTRATOR_BACKEND` ("rest" default, "gh", "fake" untuk offline).
+/// Base URL REST bisa diganti via `ORCHESTRATOR_API_URL` (mis. mock server lokal).
+pub fn from_env(timeouts: &Timeouts) -> Result<Box<dyn GhBackend>, String> {
+    match env::var("ORCHESTRATOR_BACKEND").as_deref() {
+        Err(_) | Ok("") | Ok("rest") => {
+            let base_url = env::var("ORCHESTRATOR_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+            Ok(Box::new(RestBackend::new(&base_url, timeouts)))
+        }
+        Ok("gh") => Ok(Box::new(GhCliBackend { timeouts: timeouts.clone() })),
+        Ok("fake") => Ok(Box::new(FakeBackend::default())),
+        Ok(other) => Err(format!("Backend '{}' tidak dikenal (pilih: rest, gh, fake)", other)),
+    }
+}
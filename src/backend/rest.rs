@@ -0,0 +1,206 @@
+// src/backend/rest.rs
+
+use super::{GhBackend, GhCliBackend, Timeouts};
+use crate::config::NodeConfig;
+use crate::github::{Codespace, GHError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Backend yang bicara langsung ke GitHub REST API. `gh` hanya dipakai
+/// untuk transport SSH.
+pub struct RestBackend {
+    base_url: String,
+    agent: ureq::Agent,
+    request_timeout: Duration,
+    ssh: GhCliBackend,
+}
+
+#[derive(Deserialize)]
+struct RestCodespace {
+    name: String,
+    #[serde(default)]
+    display_name: Option<String>,
+    state: String,
+    #[serde(default)]
+    machine: Option<RestMachine>,
+    #[serde(default)]
+    repository: Option<RestRepository>,
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    last_used_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    idle_timeout_minutes: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct RestMachine {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RestRepository {
+    full_name: String,
+}
+
+impl From<RestCodespace> for Codespace {
+    fn from(cs: RestCodespace) -> Self {
+        Codespace {
+            name: cs.name,
+            display_name: cs.display_name.unwrap_or_default(),
+            state: cs.state,
+            machine: cs.machine.map(|m| m.name).unwrap_or_default(),
+            repository: cs.repository.map(|r| r.full_name).unwrap_or_default(),
+            created_at: cs.created_at,
+            last_used_at: cs.last_used_at,
+            idle_timeout_minutes: cs.idle_timeout_minutes,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RestCodespaceList {
+    codespaces: Vec<RestCodespace>,
+}
+
+#[derive(Serialize)]
+struct CreateRequest<'a> {
+    machine: &'a str,
+    display_name: &'a str,
+    idle_timeout_minutes: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    devcontainer_path: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct ApiErrorBody {
+    message: String,
+}
+
+impl RestBackend {
+    pub fn new(base_url: &str, timeouts: &Timeouts) -> Self {
+        let request_timeout = Duration::from_secs_f64(timeouts.request_secs);
+        RestBackend {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(Duration::from_secs(15))
+                .timeout(request_timeout)
+                .build(),
+            request_timeout,
+            ssh: GhCliBackend { timeouts: timeouts.clone() },
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+
+    fn send(&self, token: &str, method: &str, path: &str, body: Option<String>) -> Result<String, GHError> {
+        debug!("{} {}", method, path);
+        let request = self.agent.request(method, &self.url(path))
+            .set("Authorization", &format!("Bearer {}", token))
+            .set("Accept", "application/vnd.github+json")
+            .set("X-GitHub-Api-Version", "2022-11-28")
+            .set("User-Agent", "codespace-orchestrator");
+
+        let result = match body {
+            Some(b) => request.set("Content-Type", "application/json").send_string(&b),
+            None => request.call(),
+        };
+
+        match result {
+            Ok(response) => response.into_string()
+                .map_err(|e| GHError::CommandError(format!("Failed to read response: {}", e))),
+            Err(ureq::Error::Status(status, response)) => {
+                let reset_at = rate_limit_reset(&response);
+                let raw = response.into_string().unwrap_or_default();
+                let message = serde_json::from_str::<ApiErrorBody>(&raw)
+                    .map(|b| b.message)
+                    .unwrap_or(raw);
+                if status == 403 && reset_at.is_some() {
+                    return Err(GHError::RateLimited { reset_at, message });
+                }
+                Err(GHError::from_http(status, message, reset_at))
+            }
+            Err(ureq::Error::Transport(t)) if is_timeout(&t) => Err(GHError::Timeout {
+                operation: format!("{} {}", method, path),
+                after: self.request_timeout,
+            }),
+            Err(e) => Err(GHError::CommandError(format!("Request to {} failed: {}", path, e))),
+        }
+    }
+
+    fn parse<T: for<'de> Deserialize<'de>>(body: &str, what: &str) -> Result<T, GHError> {
+        serde_json::from_str(body)
+            .map_err(|e| GHError::Parse(format!("Unexpected {} response: {}", what, e)))
+    }
+}
+
+/// Waktu reset rate limit, hanya kalau response memang menandakan limit habis.
+fn rate_limit_reset(response: &ureq::Response) -> Option<DateTime<Utc>> {
+    if let Some(secs) = response.header("retry-after").and_then(|v| v.parse::<i64>().ok()) {
+        return Some(Utc::now() + chrono::Duration::seconds(secs));
+    }
+    if response.header("x-ratelimit-remaining") != Some("0") {
+        return None;
+    }
+    response.header("x-ratelimit-reset")
+        .and_then(|v| v.parse::<i64>().ok())
+        .and_then(|epoch| DateTime::from_timestamp(epoch, 0))
+}
+
+fn is_timeout(t: &ureq::Transport) -> bool {
+    std::error::Error::source(t)
+        .and_then(|e| e.downcast_ref::<std::io::Error>())
+        .is_some_and(|e| matches!(e.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock))
+}
+
+impl GhBackend for RestBackend {
+    fn list_codespaces(&self, token: &str, repo: &str) -> Result<Vec<Codespace>, GHError> {
+        let body = self.send(token, "GET", &format!("repos/{}/codespaces?per_page=100", repo), None)?;
+        let list: RestCodespaceList = Self::parse(&body, "codespace list")?;
+        Ok(list.codespaces.into_iter().map(Codespace::from).collect())
+    }
+
+    fn create_codespace(&self, token: &str, repo: &str, node: &NodeConfig) -> Result<String, GHError> {
+        let request = CreateRequest {
+            machine: &node.machine,
+            display_name: node.display_name(),
+            idle_timeout_minutes: node.idle_timeout_minutes,
+            devcontainer_path: node.devcontainer_path.as_deref(),
+        };
+        let payload = serde_json::to_string(&request).unwrap();
+        let body = self.send(token, "POST", &format!("repos/{}/codespaces", repo), Some(payload))?;
+        let created: RestCodespace = Self::parse(&body, "create codespace")?;
+        Ok(created.name)
+    }
+
+    fn start_codespace(&self, token: &str, name: &str) -> Result<(), GHError> {
+        self.send(token, "POST", &format!("user/codespaces/{}/start", name), Some(String::new())).map(|_| ())
+    }
+
+    fn stop_codespace(&self, token: &str, name: &str) -> Result<(), GHError> {
+        self.send(token, "POST", &format!("user/codespaces/{}/stop", name), Some(String::new())).map(|_| ())
+    }
+
+    fn delete_codespace(&self, token: &str, name: &str) -> Result<(), GHError> {
+        self.send(token, "DELETE", &format!("user/codespaces/{}", name), None).map(|_| ())
+    }
+
+    fn view_codespace(&self, token: &str, name: &str) -> Result<Codespace, GHError> {
+        let body = self.send(token, "GET", &format!("user/codespaces/{}", name), None)?;
+        let cs: RestCodespace = Self::parse(&body, "codespace")?;
+        Ok(cs.into())
+    }
+
+    fn ssh(&self, token: &str, name: &str, cmd: &str) -> Result<String, GHError> {
+        self.ssh.ssh(token, name, cmd)
+    }
+
+    fn api(&self, token: &str, endpoint: &str) -> Result<String, GHError> {
+        self.send(token, "GET", endpoint, None)
+    }
+}
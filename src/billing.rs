@@ -1,12 +1,342 @@
 // src/billing.rs
 
-use std::process::Command;
-use serde::Deserialize;
+use crate::backend::GhBackend;
+use crate::forecast::ForecastConfig;
+use crate::guard::GuardConfig;
+use crate::pricing::Prices;
+use crate::retry::RetryPolicy;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct BillingInfo {
     pub total_core_hours_used: f32,
-    pub is_quota_ok: bool,
+    pub included_core_hours: f32,
+    pub storage_gb_months_used: f32,
+    pub included_storage_gb_months: f32,
+    pub period: BillingPeriod,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Siapa yang membayar codespace di repo target.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BillingOwner {
+    User(String),
+    Org(String),
+    /// Org yang billing-nya lewat enterprise (`billing.enterprise`).
+    Enterprise(String),
+}
+
+impl BillingOwner {
+    fn usage_endpoint(&self) -> String {
+        match self {
+            BillingOwner::User(login) => format!("/users/{}/settings/billing/usage", login),
+            BillingOwner::Org(login) => format!("/organizations/{}/settings/billing/usage", login),
+            BillingOwner::Enterprise(slug) => format!("/enterprises/{}/settings/billing/usage", slug),
+        }
+    }
+
+    /// Key di cache, history dan `billing.accounts`. Org/enterprise diberi
+    /// prefix supaya tidak bentrok dengan username.
+    pub fn key(&self) -> String {
+        match self {
+            BillingOwner::User(login) => login.clone(),
+            BillingOwner::Org(login) => format!("org:{}", login),
+            BillingOwner::Enterprise(slug) => format!("enterprise:{}", slug),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        match key.split_once(':') {
+            Some(("org", login)) => BillingOwner::Org(login.to_string()),
+            Some(("enterprise", slug)) => BillingOwner::Enterprise(slug.to_string()),
+            _ => BillingOwner::User(key.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for BillingOwner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BillingOwner::User(login) => write!(f, "@{}", login),
+            BillingOwner::Org(login) => write!(f, "org {}", login),
+            BillingOwner::Enterprise(slug) => write!(f, "enterprise {}", slug),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NewCodespaceDefaults {
+    billable_owner: BillableOwner,
+}
+
+#[derive(Deserialize)]
+struct BillableOwner {
+    login: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// Tanya GitHub siapa yang akan ditagih kalau `username` membuat codespace
+/// di `repo`. Kalau gagal, anggap ditagih ke user sendiri.
+pub fn detect_owner(gh: &dyn GhBackend, token: &str, repo: &str, username: &str, config: &BillingConfig) -> BillingOwner {
+    let defaults = gh.api(token, &format!("/repos/{}/codespaces/new", repo))
+        .map_err(|e| e.to_string())
+        .and_then(|body| serde_json::from_str::<NewCodespaceDefaults>(&body).map_err(|e| e.to_string()));
+    match defaults {
+        Ok(d) if d.billable_owner.kind == "Organization" => match &config.enterprise {
+            Some(slug) => BillingOwner::Enterprise(slug.clone()),
+            None => BillingOwner::Org(d.billable_owner.login),
+        },
+        Ok(_) => BillingOwner::User(username.to_string()),
+        Err(e) => {
+            warn!("Pemilik billing {} tidak terdeteksi ({}), pakai billing user.", repo, e.lines().next().unwrap_or(""));
+            BillingOwner::User(username.to_string())
+        }
+    }
+}
+
+/// Usage mentah satu periode sebelum dibandingkan dengan kuota. Ini yang di-cache.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Usage {
+    pub core_hours: f32,
+    pub storage_gb_months: f32,
+    pub period: BillingPeriod,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Satu bulan billing GitHub (UTC). Kuota di-reset setiap tanggal 1.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BillingPeriod {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl BillingPeriod {
+    pub fn current() -> Self {
+        let today = Utc::now().date_naive();
+        BillingPeriod { year: today.year(), month: today.month() }
+    }
+
+    pub fn start(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.year, self.month, 1).unwrap()
+    }
+
+    /// Tanggal kuota di-reset, yaitu awal periode berikutnya.
+    pub fn resets_on(&self) -> NaiveDate {
+        let (year, month) = if self.month == 12 { (self.year + 1, 1) } else { (self.year, self.month + 1) };
+        NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.start() && date < self.resets_on()
+    }
+
+    /// Hari yang sudah lewat termasuk hari ini, minimal 1.
+    pub fn days_elapsed(&self, today: NaiveDate) -> i64 {
+        ((today - self.start()).num_days() + 1).max(1)
+    }
+
+    pub fn days_left(&self, today: NaiveDate) -> i64 {
+        (self.resets_on() - today).num_days().max(0)
+    }
+}
+
+impl BillingInfo {
+    /// Compute dan storage sama-sama masih di bawah kuota.
+    pub fn is_quota_ok(&self) -> bool {
+        self.total_core_hours_used < self.included_core_hours
+            && self.storage_gb_months_used < self.included_storage_gb_months
+    }
+}
+
+/// Kuota gratis per akun. Default sesuai GitHub Free.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct Allowance {
+    pub core_hours: f32,
+    pub storage_gb_months: f32,
+}
+
+impl Default for Allowance {
+    fn default() -> Self {
+        Allowance { core_hours: 120.0, storage_gb_months: 15.0 }
+    }
+}
+
+/// Override kuota untuk satu akun (misalnya plan Pro). Field kosong ikut default.
+#[derive(Deserialize, Clone, Copy, Default)]
+pub struct AllowanceOverride {
+    pub core_hours: Option<f32>,
+    pub storage_gb_months: Option<f32>,
+}
+
+/// Pengali core per SKU compute Codespaces (machine 2 sampai 32 core).
+const COMPUTE_SKUS: &[(&str, f32)] = &[
+    ("compute 2-core", 2.0),
+    ("compute 4-core", 4.0),
+    ("compute 8-core", 8.0),
+    ("compute 16-core", 16.0),
+    ("compute 32-core", 32.0),
+];
+
+/// SKU storage Codespaces, termasuk storage prebuild.
+const STORAGE_SKUS: &[&str] = &["prebuild storage", "storage"];
+
+/// GitHub menghitung satu bulan billing sebagai 730 jam.
+const HOURS_PER_MONTH: f32 = 730.0;
+
+enum Sku {
+    Compute(f32),
+    Storage,
+}
+
+fn classify_sku(sku: &str) -> Option<Sku> {
+    let sku = sku.to_lowercase();
+    // Cocokkan suffix supaya "compute 2-core" tidak ikut "compute 32-core".
+    if let Some((_, cores)) = COMPUTE_SKUS.iter().find(|(name, _)| sku.ends_with(name)) {
+        return Some(Sku::Compute(*cores));
+    }
+    if STORAGE_SKUS.iter().any(|name| sku.ends_with(name)) {
+        return Some(Sku::Storage);
+    }
+    None
+}
+
+/// Hasil cek kuota. `Unknown` berarti usage tidak bisa dibaca, bukan habis.
+#[derive(Debug, Clone)]
+pub enum QuotaStatus {
+    Ok(BillingInfo),
+    Exhausted(BillingInfo),
+    Unknown(String),
+}
+
+/// Apa yang dilakukan main loop kalau kuota tidak bisa diverifikasi.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownPolicy {
+    /// Anggap tidak aman, pindah ke token berikutnya.
+    #[default]
+    Skip,
+    /// Tetap deploy dengan token ini.
+    Proceed,
+    /// Cek ulang nanti sesuai `unknown_retry`, lalu skip kalau tetap gagal.
+    RetryLater,
+}
+
+/// Object `billing` di config.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct BillingConfig {
+    pub included: Allowance,
+    /// Key = username, `org:<login>` atau `enterprise:<slug>`.
+    pub accounts: BTreeMap<String, AllowanceOverride>,
+    pub on_unknown: UnknownPolicy,
+    pub unknown_retry: RetryPolicy,
+    pub forecast: ForecastConfig,
+    /// Snapshot usage harian per akun, untuk trend di `billing`.
+    pub history_path: String,
+    /// Hasil billing terakhir per akun.
+    pub cache_path: String,
+    /// Umur maksimal cache. 0 = selalu panggil API.
+    pub cache_ttl_minutes: f64,
+    /// Slug enterprise untuk codespace yang ditagih ke org di bawah enterprise.
+    pub enterprise: Option<String>,
+    pub prices: Prices,
+    /// Batas USD per periode untuk usage di atas kuota gratis. Kosong = forecast
+    /// hanya mengizinkan kuota gratis.
+    pub max_budget_usd: Option<f64>,
+    pub guard: GuardConfig,
+}
+
+impl Default for BillingConfig {
+    fn default() -> Self {
+        BillingConfig {
+            included: Allowance::default(),
+            accounts: BTreeMap::new(),
+            on_unknown: UnknownPolicy::default(),
+            unknown_retry: RetryPolicy {
+                max_attempts: 4,
+                initial_delay_secs: 5.0 * 60.0,
+                max_delay_secs: 30.0 * 60.0,
+                multiplier: 2.0,
+                jitter: 0.1,
+            },
+            forecast: ForecastConfig::default(),
+            history_path: "billing_history.json".to_string(),
+            cache_path: "billing_cache.json".to_string(),
+            cache_ttl_minutes: 30.0,
+            enterprise: None,
+            prices: Prices::default(),
+            max_budget_usd: None,
+            guard: GuardConfig::default(),
+        }
+    }
+}
+
+impl BillingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.cache_ttl_minutes.is_finite() || !(0.0..=24.0 * 60.0).contains(&self.cache_ttl_minutes) {
+            return Err("billing.cache_ttl_minutes harus angka 0-1440".to_string());
+        }
+        if self.max_budget_usd.is_some_and(|b| !b.is_finite() || b < 0.0) {
+            return Err("billing.max_budget_usd harus angka >= 0".to_string());
+        }
+        self.prices.validate()?;
+        let mut values = vec![
+            ("billing.included.core_hours".to_string(), Some(self.included.core_hours)),
+            ("billing.included.storage_gb_months".to_string(), Some(self.included.storage_gb_months)),
+        ];
+        for (user, o) in &self.accounts {
+            values.push((format!("billing.accounts.{}.core_hours", user), o.core_hours));
+            values.push((format!("billing.accounts.{}.storage_gb_months", user), o.storage_gb_months));
+        }
+        for (name, value) in values {
+            if let Some(v) = value {
+                if !v.is_finite() || v < 0.0 {
+                    return Err(format!("{} harus angka >= 0", name));
+                }
+            }
+        }
+        self.unknown_retry.validate().map_err(|e| format!("billing.unknown_retry: {}", e))?;
+        self.forecast.validate()?;
+        self.guard.validate()
+    }
+
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_secs_f64(self.cache_ttl_minutes * 60.0)
+    }
+
+    /// Kuota untuk `key` (lihat `BillingOwner::key`), dengan override per akun kalau ada.
+    /// Org dan enterprise tidak punya kuota Codespaces gratis, default-nya 0.
+    pub fn allowance_for(&self, key: &str) -> Allowance {
+        let base = match BillingOwner::from_key(key) {
+            BillingOwner::User(_) => self.included,
+            BillingOwner::Org(_) | BillingOwner::Enterprise(_) => Allowance { core_hours: 0.0, storage_gb_months: 0.0 },
+        };
+        let o = self.accounts.get(key).copied().unwrap_or_default();
+        Allowance {
+            core_hours: o.core_hours.unwrap_or(base.core_hours),
+            storage_gb_months: o.storage_gb_months.unwrap_or(base.storage_gb_months),
+        }
+    }
+
+    /// `Some(alasan)` kalau `owner` tidak punya kuota gratis dan tidak ada
+    /// `max_budget_usd`, jadi tidak ada satu jam pun yang boleh dipakai.
+    pub fn unfunded(&self, owner: &BillingOwner) -> Option<String> {
+        let key = owner.key();
+        if self.max_budget_usd.is_some() || self.allowance_for(&key).core_hours > 0.0 {
+            return None;
+        }
+        Some(format!(
+            "Codespace ditagih ke {} yang tidak punya kuota gratis. Isi billing.accounts.\"{}\" dengan kuota yang dimiliki, \
+             atau billing.max_budget_usd supaya deploy berbayar dibatasi budget dan spend guard.",
+            owner, key
+        ))
+    }
 }
 
 // Struct baru untuk parsing JSON yang Anda berikan
@@ -15,6 +345,8 @@ struct UsageItem {
     product: String,
     sku: String,
     quantity: f32,
+    #[serde(default, rename = "unitType")]
+    unit_type: String,
 }
 
 #[derive(Deserialize, Debug)]
@@ -23,78 +355,253 @@ struct BillingReport {
     usage_items: Vec<UsageItem>,
 }
 
-fn run_gh_api(token: &str, endpoint: &str) -> Result<String, String> {
-    let output = Command::new("gh")
-        .args(&["api", endpoint, "-H", "Accept: application/vnd.github+json"])
-        .env("GH_TOKEN", token)
-        .output()
-        .map_err(|e| format!("Failed to execute gh: {}", e))?;
+/// Ambil usage periode berjalan dari API. Format response user, org dan
+/// enterprise sama. Error berisi alasan untuk `Unknown`.
+pub fn fetch_usage(gh: &dyn GhBackend, token: &str, owner: &BillingOwner) -> Result<Usage, String> {
+    // Minta usage periode berjalan secara eksplisit, bukan default endpoint.
+    let period = BillingPeriod::current();
+    let endpoint = format!("{}?year={}&month={}", owner.usage_endpoint(), period.year, period.month);
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(stderr.to_string());
+    let response = gh.api(token, &endpoint)
+        .map_err(|e| format!("Gagal menghubungi API billing: {}", e.to_string().lines().next().unwrap_or("API error")))?;
+
+    let report = serde_json::from_str::<BillingReport>(&response)
+        .map_err(|e| format!("Format data billing tidak dikenal: {}", e))?;
+
+    let mut total_core_hours_used = 0.0;
+    let mut storage_gb_months_used = 0.0;
+    let mut unknown_skus: Vec<String> = Vec::new();
+    for item in report.usage_items {
+        if !item.product.eq_ignore_ascii_case("codespaces") {
+            continue;
+        }
+        match classify_sku(&item.sku) {
+            Some(Sku::Compute(cores)) => total_core_hours_used += item.quantity * cores,
+            // Storage biasanya dilaporkan dalam GB-jam.
+            Some(Sku::Storage) if item.unit_type.to_lowercase().contains("month") => storage_gb_months_used += item.quantity,
+            Some(Sku::Storage) => storage_gb_months_used += item.quantity / HOURS_PER_MONTH,
+            None if !unknown_skus.contains(&item.sku) => unknown_skus.push(item.sku),
+            None => {}
+        }
+    }
+    for sku in &unknown_skus {
+        warn!("SKU Codespaces tidak dikenal '{}', tidak dihitung.", sku);
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    Ok(Usage {
+        core_hours: total_core_hours_used,
+        storage_gb_months: storage_gb_months_used,
+        period,
+        fetched_at: Utc::now(),
+    })
 }
 
-pub fn get_billing_info(token: &str, username: &str) -> Result<BillingInfo, String> {
-    // Menggunakan endpoint baru yang valid
-    let endpoint = format!("/users/{}/settings/billing/usage", username);
-    
-    let response = match run_gh_api(token, &endpoint) {
-        Ok(r) => r,
-        Err(e) => {
-            println!("   WARNING: Gagal menghubungi API billing ({}). Anggap kuota habis.", e.lines().next().unwrap_or("API error"));
-            return Ok(BillingInfo {
-                total_core_hours_used: 999.0,
-                is_quota_ok: false,
-            });
-        }
+/// Bandingkan usage dengan kuota akun.
+pub fn evaluate(usage: &Usage, allowance: &Allowance) -> QuotaStatus {
+    let info = BillingInfo {
+        total_core_hours_used: usage.core_hours,
+        included_core_hours: allowance.core_hours,
+        storage_gb_months_used: usage.storage_gb_months,
+        included_storage_gb_months: allowance.storage_gb_months,
+        period: usage.period,
+        fetched_at: usage.fetched_at,
     };
-    
-    // Coba parse format JSON 'usageItems'
-    if let Ok(report) = serde_json::from_str::<BillingReport>(&response) {
-        let mut total_core_hours_used = 0.0;
-
-        for item in report.usage_items {
-            if item.product == "codespaces" {
-                if item.sku.contains("compute 2-core") {
-                    total_core_hours_used += item.quantity * 2.0;
-                } else if item.sku.contains("compute 4-core") {
-                    total_core_hours_used += item.quantity * 4.0;
-                }
+    if info.is_quota_ok() {
+        QuotaStatus::Ok(info)
+    } else {
+        QuotaStatus::Exhausted(info)
+    }
+}
+
+pub fn display_billing(status: &QuotaStatus, owner: &BillingOwner) {
+    match status {
+        QuotaStatus::Ok(info) | QuotaStatus::Exhausted(info) => {
+            info!("Billing {}: Used ~{:.1} of {:.1} core-hours",
+                owner,
+                info.total_core_hours_used,
+                info.included_core_hours
+            );
+            info!("Storage: ~{:.2} of {:.1} GB-month", info.storage_gb_months_used, info.included_storage_gb_months);
+            let today = Utc::now().date_naive();
+            info!("Period : {}-{:02}, reset {} ({} hari lagi)", info.period.year, info.period.month, info.period.resets_on(), info.period.days_left(today));
+            let age = (Utc::now() - info.fetched_at).num_minutes();
+            if age > 0 {
+                info!("(dari cache, {} menit lalu)", age);
+            }
+            if info.total_core_hours_used >= info.included_core_hours {
+                warn!("Kuota compute habis.");
+            }
+            if info.storage_gb_months_used >= info.included_storage_gb_months {
+                warn!("Kuota storage habis.");
+            }
+            if info.is_quota_ok() {
+                info!("Quota OK");
             }
         }
-        
-        // Asumsi kuota gratis standar GitHub adalah 120 core-hours
-        let included_core_hours = 120.0;
-        let is_quota_ok = total_core_hours_used < included_core_hours;
-        
-        return Ok(BillingInfo {
-            total_core_hours_used,
-            is_quota_ok,
-        });
-    }
-
-    // Jika parsing gagal, berarti formatnya tidak dikenal atau kosong.
-    // Kita ambil tindakan paling aman.
-    println!("   WARNING: Format data billing tidak dikenal atau kosong. Anggap kuota habis.");
-    Ok(BillingInfo {
-        total_core_hours_used: 999.0,
-        is_quota_ok: false,
-    })
+        QuotaStatus::Unknown(reason) => {
+            info!("Billing {}: tidak diketahui", owner);
+            warn!("{}", reason);
+        }
+    }
 }
 
-pub fn display_billing(billing: &BillingInfo, username: &str) {
-    println!("Billing @{}: Used ~{:.1} of 120.0 core-hours", 
-        username, 
-        billing.total_core_hours_used
-    );
-    
-    if !billing.is_quota_ok {
-        println!("   WARNING: Kuota habis atau tidak dapat diverifikasi.");
-    } else {
-        println!("   Quota OK");
+/// Satu baris output command `billing`, dipakai untuk tabel maupun JSON.
+#[derive(Serialize, Default)]
+pub struct AccountReport {
+    pub token: usize,
+    pub username: Option<String>,
+    /// Pemilik billing: "@user", "org <login>" atau "enterprise <slug>".
+    pub billed_to: Option<String>,
+    /// "ok", "exhausted", "unknown" atau "error" (token tidak bisa dipakai).
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub core_hours_used: Option<f32>,
+    pub included_core_hours: Option<f32>,
+    pub remaining_core_hours: Option<f32>,
+    pub storage_gb_months_used: Option<f32>,
+    pub included_storage_gb_months: Option<f32>,
+    pub remaining_storage_gb_months: Option<f32>,
+    pub core_hours_per_day: Option<f32>,
+    /// Estimasi USD usage di atas kuota gratis periode ini (list price).
+    pub estimated_overage_usd: Option<f64>,
+    pub period_resets_on: Option<NaiveDate>,
+    pub fetched_at: Option<DateTime<Utc>>,
+}
+
+impl AccountReport {
+    pub fn error(token: usize, reason: String) -> Self {
+        AccountReport { token, status: "error".to_string(), reason: Some(reason), ..Default::default() }
+    }
+
+    pub fn new(token: usize, username: &str, owner: &BillingOwner, status: &QuotaStatus, core_hours_per_day: Option<f32>, prices: &Prices) -> Self {
+        let mut report = AccountReport {
+            token,
+            username: Some(username.to_string()),
+            billed_to: Some(owner.to_string()),
+            ..Default::default()
+        };
+        let info = match status {
+            QuotaStatus::Ok(info) => { report.status = "ok".to_string(); info }
+            QuotaStatus::Exhausted(info) => { report.status = "exhausted".to_string(); info }
+            QuotaStatus::Unknown(reason) => {
+                report.status = "unknown".to_string();
+                report.reason = Some(reason.clone());
+                return report;
+            }
+        };
+        report.core_hours_used = Some(info.total_core_hours_used);
+        report.included_core_hours = Some(info.included_core_hours);
+        report.remaining_core_hours = Some((info.included_core_hours - info.total_core_hours_used).max(0.0));
+        report.storage_gb_months_used = Some(info.storage_gb_months_used);
+        report.included_storage_gb_months = Some(info.included_storage_gb_months);
+        report.remaining_storage_gb_months = Some((info.included_storage_gb_months - info.storage_gb_months_used).max(0.0));
+        report.core_hours_per_day = core_hours_per_day;
+        report.estimated_overage_usd = Some(prices.overage_cost(info));
+        report.period_resets_on = Some(info.period.resets_on());
+        report.fetched_at = Some(info.fetched_at);
+        report
+    }
+}
+
+fn cell(value: Option<f32>, precision: usize) -> String {
+    value.map(|v| format!("{:.*}", precision, v)).unwrap_or_else(|| "-".to_string())
+}
+
+pub fn print_table(reports: &[AccountReport]) {
+    println!("{:<3} {:<20} {:<20} {:<10} {:>15} {:>15} {:>15} {:>14} {:>10} {:>9} RESET",
+        "#", "ACCOUNT", "BILLED TO", "STATUS", "COMPUTE (ch)", "STORAGE (GBm)", "REMAINING ch", "REMAINING GBm", "TREND/DAY", "OVERAGE");
+    for r in reports {
+        println!("{:<3} {:<20} {:<20} {:<10} {:>15} {:>15} {:>15} {:>14} {:>10} {:>9} {}",
+            r.token,
+            r.username.as_deref().unwrap_or("-"),
+            r.billed_to.as_deref().unwrap_or("-"),
+            r.status,
+            format!("{} / {}", cell(r.core_hours_used, 1), cell(r.included_core_hours, 0)),
+            format!("{} / {}", cell(r.storage_gb_months_used, 2), cell(r.included_storage_gb_months, 0)),
+            cell(r.remaining_core_hours, 1),
+            cell(r.remaining_storage_gb_months, 2),
+            cell(r.core_hours_per_day, 1),
+            r.estimated_overage_usd.map(|v| format!("${:.2}", v)).unwrap_or_else(|| "-".to_string()),
+            r.period_resets_on.map(|d| d.to_string()).unwrap_or_else(|| "-".to_string()),
+        );
+    }
+    for r in reports {
+        if let Some(reason) = &r.reason {
+            println!("\n#{}: {}", r.token, reason.lines().next().unwrap_or(""));
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Usage compute `used` dari kuota `included`, storage masih kosong.
+    pub fn info(used: f32, included: f32) -> BillingInfo {
+        BillingInfo {
+            total_core_hours_used: used,
+            included_core_hours: included,
+            storage_gb_months_used: 0.0,
+            included_storage_gb_months: 15.0,
+            period: BillingPeriod::current(),
+            fetched_at: Utc::now(),
+        }
+    }
+
+    pub fn hours(h: f64) -> Duration {
+        Duration::from_secs_f64(h * 3600.0)
+    }
+
+    fn compute_cores(sku: &str) -> Option<f32> {
+        match classify_sku(sku) {
+            Some(Sku::Compute(cores)) => Some(cores),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn classify_sku_matches_core_suffix() {
+        assert_eq!(compute_cores("Compute 2-core"), Some(2.0));
+        assert_eq!(compute_cores("Codespaces Compute 32-core"), Some(32.0));
+        assert_eq!(compute_cores("compute 16-core"), Some(16.0));
+        assert_eq!(compute_cores("Compute 64-core"), None);
+    }
+
+    #[test]
+    fn classify_sku_storage() {
+        assert!(matches!(classify_sku("Storage"), Some(Sku::Storage)));
+        assert!(matches!(classify_sku("Codespaces Prebuild Storage"), Some(Sku::Storage)));
+        assert!(classify_sku("Actions Linux").is_none());
+    }
+
+    #[test]
+    fn org_and_enterprise_get_no_free_allowance() {
+        let billing = BillingConfig::default();
+        assert_eq!(billing.allowance_for("someone").core_hours, 120.0);
+        assert_eq!(billing.allowance_for("org:acme").core_hours, 0.0);
+        assert_eq!(billing.allowance_for("enterprise:big").storage_gb_months, 0.0);
+    }
+
+    #[test]
+    fn org_without_allowance_or_budget_is_unfunded() {
+        let org = BillingOwner::Org("acme".to_string());
+        assert!(BillingConfig::default().unfunded(&org).unwrap().contains("org:acme"));
+        assert!(BillingConfig::default().unfunded(&BillingOwner::User("someone".to_string())).is_none());
+
+        let budget = BillingConfig { max_budget_usd: Some(10.0), ..Default::default() };
+        assert!(budget.unfunded(&org).is_none());
+        let mut allowance = BillingConfig::default();
+        allowance.accounts.insert("org:acme".to_string(), AllowanceOverride { core_hours: Some(500.0), storage_gb_months: None });
+        assert!(allowance.unfunded(&org).is_none());
+    }
+
+    #[test]
+    fn account_override_applies_to_org() {
+        let mut billing = BillingConfig::default();
+        billing.accounts.insert("org:acme".to_string(), AllowanceOverride { core_hours: Some(500.0), storage_gb_months: None });
+        let allowance = billing.allowance_for("org:acme");
+        assert_eq!(allowance.core_hours, 500.0);
+        assert_eq!(allowance.storage_gb_months, 0.0);
     }
 }
@@ -0,0 +1,46 @@
+// src/cache.rs
+
+use crate::billing::{BillingPeriod, Usage};
+use crate::config;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Usage billing terakhir per username, supaya restart dan `status` tidak
+/// memanggil API billing lagi.
+#[derive(Serialize, Deserialize, Default)]
+pub struct BillingCache {
+    pub accounts: BTreeMap<String, Usage>,
+}
+
+impl BillingCache {
+    /// Usage yang belum lewat `ttl` dan masih di periode berjalan.
+    pub fn fresh(&self, username: &str, ttl: Duration) -> Option<&Usage> {
+        let usage = self.accounts.get(username)?;
+        let age = (Utc::now() - usage.fetched_at).to_std().unwrap_or_default();
+        (usage.period == BillingPeriod::current() && age < ttl).then_some(usage)
+    }
+
+    pub fn store(&mut self, username: &str, usage: Usage) {
+        self.accounts.insert(username.to_string(), usage);
+    }
+}
+
+/// File belum ada atau rusak = cache kosong.
+pub fn load_cache(path: &str) -> BillingCache {
+    if !Path::new(path).exists() {
+        return BillingCache::default();
+    }
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_cache(path: &str, cache: &BillingCache) -> io::Result<()> {
+    config::write_atomic(path, &serde_json::to_string_pretty(cache)?)
+}
@@ -0,0 +1,273 @@
+// src/cli.rs
+
+use crate::config::Timing;
+use crate::log;
+
+pub const USAGE: &str = "\
+Usage: orchestrator [GLOBAL FLAGS] <COMMAND> [ARGS]
+
+Commands:
+  run <owner/repo>       Full auto loop: deploy, keep-alive, rotasi token
+  status                 Tampilkan isi state, jumlah token dan cache billing
+  verify                 Cek node yang tercatat di state dan kuota token aktif
+  billing [owner/repo]   Tabel pemakaian billing setiap token (per repo: ikut billing org)
+  cleanup <owner/repo>   Hapus codespace milik orchestrator di repo
+  exec -- <command...>   Jalankan command via SSH di node yang tercatat
+  help                   Tampilkan bantuan ini
+
+Global flags:
+  --config <path>        File config (default: tokens.json)
+  --state <path>         File state (default: state.json)
+  --dry-run              Hanya print command yang mengubah sesuatu
+  --refresh              Abaikan cache billing, selalu panggil API
+  --log-level <level>    debug, info (default), warn atau error
+  --log-format <format>  text (default) atau json, satu object per baris
+  --log-file <path>      Tulis log juga ke file ini
+  --log-max-mb <N>       Rotasi file log setelah N MB (default: 10, simpan 3 file lama)
+  -h, --help             Tampilkan bantuan ini
+
+run flags:
+  --run-hours <N>            Lama satu cycle per token
+  --keep-alive-hours <N>     Jeda antar keep-alive
+  --retry-minutes <N>        Jeda sebelum deploy diulang
+  --rotation-delay-secs <N>  Jeda sebelum pindah token
+
+billing flags:
+  --json                 Output JSON, untuk dashboard
+
+cleanup flags:
+  --all                  Hapus SEMUA codespace di repo, bukan hanya milik orchestrator
+
+exec flags:
+  --node <name>          Hanya di node ini (default: semua node)
+  --                     Semua argumen sesudahnya jadi command, mis. exec -- ls -la
+
+Exit codes:
+  0  Sukses
+  1  Operasi GitHub gagal, atau verify menemukan node yang tidak jalan
+  2  Command, argumen atau flag salah
+  3  Config atau state tidak ada / tidak valid";
+
+/// Exit code yang didokumentasikan di `USAGE`.
+pub mod exit {
+    pub const OK: i32 = 0;
+    pub const FAILURE: i32 = 1;
+    pub const USAGE: i32 = 2;
+    pub const CONFIG: i32 = 3;
+}
+
+/// Flag yang berlaku untuk semua subcommand.
+pub struct GlobalOpts {
+    pub config_path: String,
+    pub state_path: String,
+    pub dry_run: bool,
+    pub refresh: bool,
+    pub log_level: log::Level,
+    pub log_format: log::Format,
+    pub log_file: Option<String>,
+    pub log_max_mb: f64,
+}
+
+impl Default for GlobalOpts {
+    fn default() -> Self {
+        GlobalOpts {
+            config_path: "tokens.json".to_string(),
+            state_path: "state.json".to_string(),
+            dry_run: false,
+            refresh: false,
+            log_level: log::Level::Info,
+            log_format: log::Format::Text,
+            log_file: None,
+            log_max_mb: 10.0,
+        }
+    }
+}
+
+/// CLI flag yang meng-override `timing` di config.
+#[derive(Default)]
+pub struct TimingFlags {
+    pub run_hours: Option<f64>,
+    pub keep_alive_hours: Option<f64>,
+    pub retry_minutes: Option<f64>,
+    pub rotation_delay_secs: Option<f64>,
+}
+
+impl TimingFlags {
+    pub fn apply(&self, timing: &mut Timing) {
+        if let Some(v) = self.run_hours { timing.run_duration_hours = v; }
+        if let Some(v) = self.keep_alive_hours { timing.keep_alive_interval_hours = v; }
+        if let Some(v) = self.retry_minutes { timing.deploy_retry_minutes = Some(v); }
+        if let Some(v) = self.rotation_delay_secs { timing.rotation_delay_secs = v; }
+    }
+}
+
+pub enum Command {
+    Run { repo: String, timing: TimingFlags },
+    Status,
+    Verify,
+    Billing { json: bool, repo: Option<String> },
+    Cleanup { repo: String, all: bool },
+    Exec { node: Option<String>, command: String },
+    Help,
+}
+
+pub struct Cli {
+    pub global: GlobalOpts,
+    pub command: Command,
+}
+
+const VALUE_FLAGS: &[&str] = &["--run-hours", "--keep-alive-hours", "--retry-minutes", "--rotation-delay-secs", "--node"];
+const SWITCH_FLAGS: &[&str] = &["--all", "--json"];
+
+/// Flag khusus subcommand yang belum dipakai; sisa di akhir parse = error.
+struct CommandFlags(Vec<(String, Option<String>)>);
+
+impl CommandFlags {
+    fn take(&mut self, flag: &str) -> Option<Option<String>> {
+        let pos = self.0.iter().position(|(f, _)| f == flag)?;
+        Some(self.0.remove(pos).1)
+    }
+
+    fn number(&mut self, flag: &str) -> Result<Option<f64>, String> {
+        match self.take(flag) {
+            Some(Some(value)) => value.parse().map(Some).map_err(|_| format!("{}: '{}' bukan angka", flag, value)),
+            _ => Ok(None),
+        }
+    }
+
+    fn finish(self, command: &str) -> Result<(), String> {
+        match self.0.first() {
+            Some((flag, _)) => Err(format!("Flag {} tidak berlaku untuk '{}'", flag, command)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Quote satu argumen untuk shell di node, supaya spasi dan karakter khusus tidak dipecah ulang.
+fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-./=:,@+%".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Parse `args` (tanpa nama program). Error berisi pesan untuk user.
+pub fn parse(args: &[String]) -> Result<Cli, String> {
+    let mut global = GlobalOpts::default();
+    let mut flags = CommandFlags(Vec::new());
+    let mut positionals: Vec<String> = Vec::new();
+    let mut help = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| iter.next().cloned().ok_or_else(|| format!("{} butuh nilai", flag));
+        match arg.as_str() {
+            "-h" | "--help" => help = true,
+            "--dry-run" => global.dry_run = true,
+            "--refresh" => global.refresh = true,
+            "--config" => global.config_path = value(arg)?,
+            "--state" => global.state_path = value(arg)?,
+            "--log-level" => global.log_level = log::Level::parse(&value(arg)?)?,
+            "--log-format" => global.log_format = log::Format::parse(&value(arg)?)?,
+            "--log-file" => global.log_file = Some(value(arg)?),
+            "--log-max-mb" => {
+                let v = value(arg)?;
+                global.log_max_mb = match v.parse::<f64>() {
+                    Ok(mb) if mb.is_finite() && mb > 0.0 => mb,
+                    _ => return Err(format!("--log-max-mb: '{}' harus angka > 0", v)),
+                };
+            }
+            "--" => {
+                positionals.extend(iter.by_ref().cloned());
+            }
+            flag if VALUE_FLAGS.contains(&flag) => {
+                let v = value(flag)?;
+                flags.0.push((flag.to_string(), Some(v)));
+            }
+            flag if SWITCH_FLAGS.contains(&flag) => flags.0.push((flag.to_string(), None)),
+            flag if flag.starts_with('-') => return Err(format!("Flag tidak dikenal: {}", flag)),
+            _ => positionals.push(arg.clone()),
+        }
+    }
+
+    if help {
+        return Ok(Cli { global, command: Command::Help });
+    }
+
+    let mut positionals = positionals.into_iter();
+    let Some(name) = positionals.next() else {
+        return Err("Command belum dikasih".to_string());
+    };
+    let rest: Vec<String> = positionals.collect();
+    let repo_arg = |rest: &[String]| match rest {
+        [repo] => Ok(repo.clone()),
+        [] => Err(format!("'{}' butuh argumen <owner/repo>", name)),
+        _ => Err(format!("'{}' hanya menerima satu repo", name)),
+    };
+    let no_args = |rest: &[String]| match rest {
+        [] => Ok(()),
+        _ => Err(format!("'{}' tidak menerima argumen", name)),
+    };
+
+    let command = match name.as_str() {
+        "run" => Command::Run {
+            repo: repo_arg(&rest)?,
+            timing: TimingFlags {
+                run_hours: flags.number("--run-hours")?,
+                keep_alive_hours: flags.number("--keep-alive-hours")?,
+                retry_minutes: flags.number("--retry-minutes")?,
+                rotation_delay_secs: flags.number("--rotation-delay-secs")?,
+            },
+        },
+        "status" => { no_args(&rest)?; Command::Status }
+        "verify" => { no_args(&rest)?; Command::Verify }
+        "billing" => Command::Billing {
+            json: flags.take("--json").is_some(),
+            repo: match rest.as_slice() {
+                [] => None,
+                _ => Some(repo_arg(&rest)?),
+            },
+        },
+        "cleanup" => Command::Cleanup { repo: repo_arg(&rest)?, all: flags.take("--all").is_some() },
+        "exec" => {
+            if rest.is_empty() {
+                return Err("'exec' butuh command".to_string());
+            }
+            Command::Exec { node: flags.take("--node").flatten(), command: rest.iter().map(|a| shell_quote(a)).collect::<Vec<_>>().join(" ") }
+        }
+        "help" => Command::Help,
+        other if other.contains('/') => return Err(format!("Command tidak dikenal: '{}'. Maksudnya 'run {}'?", other, other)),
+        other => return Err(format!("Command tidak dikenal: '{}'", other)),
+    };
+    flags.finish(&name)?;
+
+    Ok(Cli { global, command })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exec_command(args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        match parse(&args).unwrap().command {
+            Command::Exec { command, .. } => command,
+            _ => panic!("bukan exec"),
+        }
+    }
+
+    #[test]
+    fn exec_quotes_each_argument() {
+        assert_eq!(exec_command(&["exec", "--", "ls", "-la"]), "ls -la");
+        assert_eq!(exec_command(&["exec", "--", "echo", "a b", "it's", ""]), "echo 'a b' 'it'\\''s' ''");
+        assert_eq!(exec_command(&["exec", "--", "sh", "-c", "tail log | grep x"]), "sh -c 'tail log | grep x'");
+    }
+
+    #[test]
+    fn exec_flags_need_separator() {
+        let args: Vec<String> = ["exec", "ls", "-la"].iter().map(|a| a.to_string()).collect();
+        assert!(parse(&args).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+// src/clock.rs
+
+use chrono::{DateTime, Utc};
+use std::thread;
+use std::time::Duration;
+
+/// Sumber waktu main loop. Test memakai jam palsu supaya satu cycle penuh
+/// selesai tanpa benar-benar menunggu.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+    fn sleep(&self, duration: Duration);
+}
+
+/// Jam sistem dan `thread::sleep` biasa.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Jam yang hanya maju saat `sleep` dipanggil.
+    pub struct FakeClock(Cell<DateTime<Utc>>);
+
+    impl FakeClock {
+        pub fn new() -> Self {
+            FakeClock(Cell::new(Utc::now()))
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Utc> {
+            self.0.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.0.set(self.0.get() + chrono::Duration::from_std(duration).unwrap());
+        }
+    }
+}
@@ -1,18 +1,217 @@
+use chrono::{DateTime, Utc};
+use crate::backend::Timeouts;
+use crate::billing::BillingConfig;
+use crate::retry::RetryConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::Path;
+use std::time::Duration;
+
+const DEFAULT_STARTUP_COMMAND: &str = "bash -l -c 'bash /workspaces/mawari-nexus-blueprint/auto-start.sh'";
 
 #[derive(Deserialize)]
 pub struct Config {
     pub tokens: Vec<String>,
+    #[serde(default = "default_nodes")]
+    pub nodes: Vec<NodeConfig>,
+    #[serde(default)]
+    pub strategy: Strategy,
+    /// Opt-in: stop/delete semua codespace di repo, termasuk yang bukan buatan orchestrator.
+    #[serde(default)]
+    pub cleanup_all: bool,
+    #[serde(default)]
+    pub timing: Timing,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub billing: BillingConfig,
+    #[serde(default)]
+    pub timeouts: Timeouts,
+}
+
+/// Durasi dan jeda di main loop. Bisa di-override lewat CLI flag.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct Timing {
+    /// Lama satu cycle per token sebelum rotasi.
+    pub run_duration_hours: f64,
+    /// Jeda antar keep-alive, harus lebih pendek dari idle timeout node.
+    pub keep_alive_interval_hours: f64,
+    /// Jeda sebelum deploy diulang setelah gagal. Kalau diisi, menggantikan
+    /// `retry.deploy.initial_delay_secs`.
+    pub deploy_retry_minutes: Option<f64>,
+    /// Jeda sebelum pindah ke token berikutnya.
+    pub rotation_delay_secs: f64,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing {
+            run_duration_hours: 20.0,
+            keep_alive_interval_hours: 3.5,
+            deploy_retry_minutes: None,
+            rotation_delay_secs: 3.0,
+        }
+    }
+}
+
+impl Timing {
+    pub fn run_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.run_duration_hours * 3600.0)
+    }
+
+    pub fn keep_alive_interval(&self) -> Duration {
+        Duration::from_secs_f64(self.keep_alive_interval_hours * 3600.0)
+    }
+
+    pub fn rotation_delay(&self) -> Duration {
+        Duration::from_secs_f64(self.rotation_delay_secs)
+    }
+
+    /// Keep-alive harus terjadi sebelum codespace mati karena idle.
+    pub fn validate(&self, nodes: &[NodeConfig]) -> Result<(), String> {
+        // Batas atas supaya konversi ke `Duration` tidak overflow.
+        let values = [
+            ("run_duration_hours", self.run_duration_hours, 24.0 * 365.0),
+            ("keep_alive_interval_hours", self.keep_alive_interval_hours, 24.0),
+            ("deploy_retry_minutes", self.deploy_retry_minutes.unwrap_or(0.0), 24.0 * 60.0),
+            ("rotation_delay_secs", self.rotation_delay_secs, 3600.0),
+        ];
+        for (name, value, max) in values {
+            if !value.is_finite() || !(0.0..=max).contains(&value) {
+                return Err(format!("{} harus angka 0-{}", name, max));
+            }
+        }
+        if self.run_duration_hours == 0.0 || self.keep_alive_interval_hours == 0.0 {
+            return Err("run_duration_hours dan keep_alive_interval_hours harus > 0".to_string());
+        }
+        let interval_minutes = self.keep_alive_interval_hours * 60.0;
+        for node in nodes {
+            if interval_minutes >= node.idle_timeout_minutes as f64 {
+                return Err(format!(
+                    "keep_alive_interval_hours ({}) harus lebih pendek dari idle_timeout_minutes node '{}' ({} menit)",
+                    self.keep_alive_interval_hours, node.name, node.idle_timeout_minutes
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Cara deploy di awal setiap cycle.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    /// Hapus semua codespace di repo lalu buat baru.
+    #[default]
+    Nuke,
+    /// Pakai ulang codespace yang sehat, hanya buat/start yang perlu.
+    Reconcile,
+}
+
+/// Satu workload yang dijalankan di codespace sendiri.
+#[derive(Deserialize, Clone)]
+pub struct NodeConfig {
+    /// Key unik, dipakai di state.json.
+    pub name: String,
+    /// Default sama dengan `name`.
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub machine: String,
+    /// Jumlah core untuk forecast kalau machine tidak ada di tabel.
+    #[serde(default)]
+    pub cores: Option<u32>,
+    #[serde(default = "default_idle_timeout")]
+    pub idle_timeout_minutes: u32,
+    #[serde(default)]
+    pub devcontainer_path: Option<String>,
+    /// Command yang dijalankan via SSH setelah codespace siap, dan di setiap keep-alive.
+    #[serde(default = "default_startup_command")]
+    pub startup_command: String,
+}
+
+impl NodeConfig {
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+fn default_idle_timeout() -> u32 {
+    240
+}
+
+fn default_startup_command() -> String {
+    DEFAULT_STARTUP_COMMAND.to_string()
+}
+
+fn default_nodes() -> Vec<NodeConfig> {
+    vec![
+        NodeConfig {
+            name: "mawari".to_string(),
+            display_name: Some("mawari-node".to_string()),
+            machine: "basicLinux32gb".to_string(),
+            cores: None,
+            idle_timeout_minutes: default_idle_timeout(),
+            devcontainer_path: None,
+            startup_command: default_startup_command(),
+        },
+        NodeConfig {
+            name: "nexus".to_string(),
+            display_name: Some("nexus-node".to_string()),
+            machine: "standardLinux32gb".to_string(),
+            cores: None,
+            idle_timeout_minutes: default_idle_timeout(),
+            devcontainer_path: None,
+            startup_command: default_startup_command(),
+        },
+    ]
 }
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct State {
     pub current_account_index: usize,
-    pub current_mawari_name: String,
-    pub current_nexus_name: String,
+    /// Nama node -> nama codespace yang sedang jalan.
+    #[serde(default)]
+    pub nodes: BTreeMap<String, String>,
+    /// Semua codespace yang dibuat orchestrator dan belum dihapus.
+    #[serde(default)]
+    pub owned_codespaces: Vec<String>,
+    /// Cycle yang sedang berjalan, supaya restart bisa melanjutkan keep-alive.
+    #[serde(default)]
+    pub cycle_started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub cycle_deadline: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_keep_alive_at: Option<DateTime<Utc>>,
+    /// Estimasi USD per jam node cycle ini (list price), untuk biaya kumulatif.
+    #[serde(default)]
+    pub cycle_hourly_usd: Option<f64>,
+    /// Total core node cycle ini (setelah downgrade), untuk spend guard.
+    #[serde(default)]
+    pub cycle_cores: Option<u32>,
+    /// Spend guard sudah men-stop node; sisa cycle tidak ada keep-alive.
+    #[serde(default)]
+    pub guard_paused_at: Option<DateTime<Utc>>,
+    // Format lama (sebelum node deklaratif), hanya dibaca untuk migrasi.
+    #[serde(default, skip_serializing)]
+    current_mawari_name: String,
+    #[serde(default, skip_serializing)]
+    current_nexus_name: String,
+}
+
+impl State {
+    /// Cycle selesai: pindah ke token berikutnya dan lupakan data cycle lama.
+    pub fn end_cycle(&mut self, token_count: usize) {
+        self.current_account_index = (self.current_account_index + 1) % token_count.max(1);
+        self.cycle_started_at = None;
+        self.cycle_deadline = None;
+        self.last_keep_alive_at = None;
+        self.cycle_hourly_usd = None;
+        self.cycle_cores = None;
+        self.guard_paused_at = None;
+    }
 }
 
 pub fn load_config(path: &str) -> io::Result<Config> {
@@ -37,29 +236,124 @@ pub fn load_config(path: &str) -> io::Result<Config> {
         ));
     }
     
+    if config.nodes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Array 'nodes' kosong di tokens.json"
+        ));
+    }
+    
+    let mut seen = HashSet::new();
+    for node in &config.nodes {
+        if !seen.insert(node.name.as_str()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Nama node '{}' duplikat", node.name)
+            ));
+        }
+        if !(5..=240).contains(&node.idle_timeout_minutes) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Node '{}': idle_timeout_minutes harus 5-240", node.name)
+            ));
+        }
+    }
+    
+    config.timing.validate(&config.nodes)
+        .and_then(|_| config.retry.validate())
+        .and_then(|_| config.billing.validate())
+        .and_then(|_| config.timeouts.validate())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    
     // Validasi format token
     for (i, token) in config.tokens.iter().enumerate() {
         if !token.starts_with("ghp_") && !token.starts_with("github_pat_") {
-            eprintln!("⚠️  WARNING: Token #{} mungkin tidak valid (tidak diawali ghp_ atau github_pat_)", i + 1);
+            warn!("Token #{} mungkin tidak valid (tidak diawali ghp_ atau github_pat_)", i + 1);
         }
     }
     
     Ok(config)
 }
 
+/// File belum ada = state kosong. File ada tapi rusak = error, jangan
+/// diam-diam mulai dari token 0 dan lupa codespace yang sedang jalan.
 pub fn load_state(path: &str) -> io::Result<State> {
     if !Path::new(path).exists() {
         return Ok(State::default());
     }
     
     let data = fs::read_to_string(path)?;
-    let state: State = serde_json::from_str(&data)
-        .unwrap_or_default();
+    let mut state: State = serde_json::from_str(&data)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} rusak ({}). Cek backup di {} atau hapus file untuk mulai dari awal", path, e, backup_path(path))
+        ))?;
+    
+    if state.nodes.is_empty() {
+        let legacy = [
+            ("mawari", std::mem::take(&mut state.current_mawari_name)),
+            ("nexus", std::mem::take(&mut state.current_nexus_name)),
+        ];
+        for (node, codespace) in legacy {
+            if !codespace.is_empty() {
+                state.nodes.insert(node.to_string(), codespace);
+            }
+        }
+    }
+
+    // Codespace yang tercatat sebagai node pasti dibuat oleh orchestrator.
+    for codespace in state.nodes.values() {
+        if !state.owned_codespaces.contains(codespace) {
+            state.owned_codespaces.push(codespace.clone());
+        }
+    }
     Ok(state)
 }
 
+pub fn backup_path(path: &str) -> String {
+    format!("{}.bak", path)
+}
+
+/// Tulis file lewat file sementara + rename, tanpa backup.
+pub fn write_atomic(path: &str, data: &str) -> io::Result<()> {
+    let tmp_path = format!("{}.tmp", path);
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(data.as_bytes())?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
+/// Tulis lewat `write_atomic`, supaya crash di tengah tulis tidak
+/// meninggalkan state setengah jadi. State sebelumnya disalin ke `.bak`.
 pub fn save_state(path: &str, state: &State) -> io::Result<()> {
     let data = serde_json::to_string_pretty(state)?;
-    fs::write(path, data)?;
-    Ok(())
+    if Path::new(path).exists() {
+        fs::copy(path, backup_path(path))?;
+    }
+    write_atomic(path, &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing_rejects_values_too_large_for_duration() {
+        let timing = Timing { run_duration_hours: 1e20, ..Default::default() };
+        assert!(timing.validate(&[]).is_err());
+        let timing = Timing { deploy_retry_minutes: Some(f64::MAX), ..Default::default() };
+        assert!(timing.validate(&[]).is_err());
+        assert!(Timing::default().validate(&[]).is_ok());
+    }
+
+    #[test]
+    fn timeouts_fill_missing_fields_with_defaults() {
+        let timeouts: Timeouts = serde_json::from_str(r#"{ "ssh_secs": 600 }"#).unwrap();
+        assert_eq!(timeouts.ssh_secs, 600.0);
+        assert_eq!(timeouts.create_secs, Timeouts::default().create_secs);
+        assert!(timeouts.validate().is_ok());
+        assert!(Timeouts { list_secs: 0.0, ..Default::default() }.validate().is_err());
+    }
 }
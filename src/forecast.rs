@@ -0,0 +1,253 @@
+// src/forecast.rs
+
+use crate::billing::{BillingConfig, BillingInfo};
+use crate::config::NodeConfig;
+use crate::pricing;
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::time::Duration;
+
+/// Jumlah core per machine type Codespaces.
+const MACHINE_CORES: &[(&str, u32)] = &[
+    ("basicLinux32gb", 2),
+    ("standardLinux32gb", 4),
+    ("premiumLinux", 8),
+    ("largePremiumLinux", 16),
+    ("xLargePremiumLinux", 32),
+];
+
+/// Machine paling kecil, tujuan downgrade.
+const SMALLEST_MACHINE: (&str, u32) = ("basicLinux32gb", 2);
+
+/// Apa yang dilakukan kalau sisa kuota tidak cukup untuk satu cycle penuh.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortfallPolicy {
+    /// Persingkat run sampai muat di sisa kuota.
+    #[default]
+    Shorten,
+    /// Turunkan machine node terbesar ke 2-core sampai muat.
+    Downgrade,
+    /// Jangan deploy, pindah ke token berikutnya.
+    Refuse,
+}
+
+/// Object `billing.forecast` di config.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ForecastConfig {
+    pub on_shortfall: ShortfallPolicy,
+    /// Run yang dipersingkat di bawah ini dianggap tidak layak, deploy ditolak.
+    pub min_run_hours: f64,
+}
+
+impl Default for ForecastConfig {
+    fn default() -> Self {
+        ForecastConfig { on_shortfall: ShortfallPolicy::default(), min_run_hours: 1.0 }
+    }
+}
+
+impl ForecastConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.min_run_hours.is_finite() || self.min_run_hours < 0.0 {
+            return Err("billing.forecast.min_run_hours harus angka >= 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Hasil forecast: node dan durasi yang benar-benar dipakai, atau alasan menolak.
+pub enum Decision {
+    Deploy { nodes: Vec<NodeConfig>, run: Duration },
+    Refuse(String),
+}
+
+pub fn machine_cores(node: &NodeConfig) -> Option<u32> {
+    node.cores.or_else(|| {
+        MACHINE_CORES.iter()
+            .find(|(machine, _)| machine.eq_ignore_ascii_case(&node.machine))
+            .map(|(_, cores)| *cores)
+    })
+}
+
+pub fn total_cores(nodes: &[NodeConfig]) -> Option<u32> {
+    nodes.iter().map(machine_cores).sum()
+}
+
+/// Sisa `max_budget_usd` periode ini setelah dikurangi overage yang sudah terpakai.
+fn budget_left(info: &BillingInfo, billing: &BillingConfig) -> f64 {
+    let spent = billing.prices.overage_cost(info);
+    billing.max_budget_usd.map(|b| (b - spent).max(0.0)).unwrap_or(0.0)
+}
+
+/// Core-hours yang bisa dibeli dengan `budget_left` USD pada harga machine `nodes`.
+fn budget_core_hours(nodes: &[NodeConfig], cores: u32, budget_left: f64, billing: &BillingConfig) -> f64 {
+    let rate = billing.prices.nodes_hourly(nodes).unwrap_or(0.0) / cores.max(1) as f64;
+    if budget_left > 0.0 && rate > 0.0 { budget_left / rate } else { 0.0 }
+}
+
+/// Core-hours yang boleh dipakai `nodes`: sisa kuota gratis ditambah sisa budget.
+fn capacity(nodes: &[NodeConfig], cores: u32, free: f64, budget_left: f64, billing: &BillingConfig) -> f64 {
+    free + budget_core_hours(nodes, cores, budget_left, billing)
+}
+
+/// Bandingkan core-hours yang akan dipakai cycle ini dengan sisa kuota (dan budget).
+pub fn plan(nodes: &[NodeConfig], run: Duration, info: &BillingInfo, billing: &BillingConfig) -> Decision {
+    let decision = decide(nodes, run, info, billing);
+    if let Decision::Deploy { nodes, run } = &decision {
+        let hours = run.as_secs_f64() / 3600.0;
+        pricing::print_cycle_estimate(nodes, hours, &billing.prices);
+        if let (Some(cores), Some(hourly)) = (total_cores(nodes), billing.prices.nodes_hourly(nodes)) {
+            let free = (info.included_core_hours - info.total_core_hours_used).max(0.0) as f64;
+            let paid = (cores as f64 * hours - free).max(0.0);
+            info!("Di atas kuota gratis: ~{:.1} core-hours = ~${:.2}", paid, paid * hourly / cores.max(1) as f64);
+        }
+    }
+    decision
+}
+
+fn decide(nodes: &[NodeConfig], run: Duration, info: &BillingInfo, billing: &BillingConfig) -> Decision {
+    let config = &billing.forecast;
+    let free = (info.included_core_hours - info.total_core_hours_used).max(0.0) as f64;
+    let spent = billing.prices.overage_cost(info);
+    let budget_left = budget_left(info, billing);
+    let hours = run.as_secs_f64() / 3600.0;
+    let Some(cores) = total_cores(nodes) else {
+        // Dengan budget, cycle tanpa estimasi biaya bisa berjalan tanpa batas di atas kuota.
+        if billing.max_budget_usd.is_some() {
+            return Decision::Refuse("Machine type tidak dikenal, biaya tidak bisa dihitung terhadap max_budget_usd. Isi `cores` di node.".to_string());
+        }
+        info!("Forecast dilewati: machine type tidak dikenal, isi `cores` di node.");
+        return Decision::Deploy { nodes: nodes.to_vec(), run };
+    };
+
+    let needed = cores as f64 * hours;
+    let available = capacity(nodes, cores, free, budget_left, billing);
+    info!("Forecast: {} core x {:.1}h = {:.1} core-hours, sisa kuota {:.1}", cores, hours, needed, free);
+    if let Some(budget) = billing.max_budget_usd {
+        info!("Budget ${:.2}, terpakai ~${:.2}, sisa ~${:.2} (~{:.1} core-hours)", budget, spent, budget_left, available - free);
+    }
+    if needed <= available {
+        info!("Forecast OK");
+        return Decision::Deploy { nodes: nodes.to_vec(), run };
+    }
+
+    match config.on_shortfall {
+        ShortfallPolicy::Shorten => {
+            let max_hours = available / cores as f64;
+            if max_hours < config.min_run_hours {
+                return Decision::Refuse(format!(
+                    "Sisa {:.1} core-hours hanya cukup {:.1} jam untuk {} core (min_run_hours {})",
+                    available, max_hours, cores, config.min_run_hours
+                ));
+            }
+            info!("Run dipersingkat dari {:.1} jadi {:.1} jam.", hours, max_hours);
+            Decision::Deploy { nodes: nodes.to_vec(), run: Duration::from_secs_f64(max_hours * 3600.0) }
+        }
+        ShortfallPolicy::Downgrade => {
+            let mut planned = nodes.to_vec();
+            let mut order: Vec<usize> = (0..planned.len()).collect();
+            order.sort_by_key(|&idx| Reverse(machine_cores(&planned[idx])));
+            let mut cores = cores;
+            for idx in order {
+                if cores as f64 * hours <= capacity(&planned, cores, free, budget_left, billing) {
+                    break;
+                }
+                let current = machine_cores(&planned[idx]).unwrap_or(0);
+                if current <= SMALLEST_MACHINE.1 {
+                    continue;
+                }
+                info!("Downgrade {}: {} -> {}", planned[idx].name, planned[idx].machine, SMALLEST_MACHINE.0);
+                planned[idx].machine = SMALLEST_MACHINE.0.to_string();
+                planned[idx].cores = None;
+                cores = cores - current + SMALLEST_MACHINE.1;
+            }
+            let needed = cores as f64 * hours;
+            let available = capacity(&planned, cores, free, budget_left, billing);
+            if needed > available {
+                return Decision::Refuse(format!(
+                    "Setelah downgrade masih butuh {:.1} core-hours untuk {:.1} jam, yang boleh dipakai {:.1}",
+                    needed, hours, available
+                ));
+            }
+            Decision::Deploy { nodes: planned, run }
+        }
+        ShortfallPolicy::Refuse => Decision::Refuse(format!(
+            "Butuh {:.1} core-hours untuk {:.1} jam, yang boleh dipakai {:.1}",
+            needed, hours, available
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::billing::tests::{hours, info};
+    use crate::github::tests::node;
+
+    fn billing(on_shortfall: ShortfallPolicy, min_run_hours: f64) -> BillingConfig {
+        BillingConfig { forecast: ForecastConfig { on_shortfall, min_run_hours }, ..Default::default() }
+    }
+
+    fn deployed(decision: Decision) -> (Vec<NodeConfig>, f64) {
+        match decision {
+            Decision::Deploy { nodes, run } => (nodes, run.as_secs_f64() / 3600.0),
+            Decision::Refuse(reason) => panic!("ditolak: {}", reason),
+        }
+    }
+
+    #[test]
+    fn full_run_when_quota_is_enough() {
+        let nodes = [node("a", "standardLinux32gb")];
+        let (_, run) = deployed(decide(&nodes, hours(10.0), &info(0.0, 120.0), &billing(ShortfallPolicy::Shorten, 1.0)));
+        assert_eq!(run, 10.0);
+    }
+
+    #[test]
+    fn shorten_fits_run_into_remaining_quota() {
+        // 20 core-hours sisa, 4 core: 5 jam.
+        let nodes = [node("a", "standardLinux32gb")];
+        let (planned, run) = deployed(decide(&nodes, hours(10.0), &info(100.0, 120.0), &billing(ShortfallPolicy::Shorten, 1.0)));
+        assert!((run - 5.0).abs() < 1e-6);
+        assert_eq!(planned[0].machine, "standardLinux32gb");
+    }
+
+    #[test]
+    fn shorten_refuses_below_min_run_hours() {
+        let nodes = [node("a", "standardLinux32gb")];
+        assert!(matches!(decide(&nodes, hours(10.0), &info(100.0, 120.0), &billing(ShortfallPolicy::Shorten, 6.0)), Decision::Refuse(_)));
+        assert!(matches!(decide(&nodes, hours(10.0), &info(118.0, 120.0), &billing(ShortfallPolicy::Shorten, 1.0)), Decision::Refuse(_)));
+    }
+
+    #[test]
+    fn downgrade_shrinks_largest_machine_first() {
+        // 8 + 2 core x 5 jam = 50, sisa 20: cukup setelah 8-core jadi 2-core.
+        let nodes = [node("small", "basicLinux32gb"), node("big", "premiumLinux")];
+        let (planned, run) = deployed(decide(&nodes, hours(5.0), &info(100.0, 120.0), &billing(ShortfallPolicy::Downgrade, 1.0)));
+        assert_eq!(run, 5.0);
+        assert_eq!(planned[0].machine, "basicLinux32gb");
+        assert_eq!(planned[1].machine, "basicLinux32gb");
+    }
+
+    #[test]
+    fn downgrade_refuses_when_smallest_still_too_big() {
+        let nodes = [node("small", "basicLinux32gb"), node("big", "premiumLinux")];
+        assert!(matches!(decide(&nodes, hours(5.0), &info(115.0, 120.0), &billing(ShortfallPolicy::Downgrade, 1.0)), Decision::Refuse(_)));
+    }
+
+    #[test]
+    fn unknown_machine_is_refused_only_with_budget() {
+        let nodes = [node("a", "mysteryLinux")];
+        let (_, run) = deployed(decide(&nodes, hours(10.0), &info(0.0, 120.0), &billing(ShortfallPolicy::Shorten, 1.0)));
+        assert_eq!(run, 10.0);
+
+        let budget = BillingConfig { max_budget_usd: Some(5.0), ..billing(ShortfallPolicy::Shorten, 1.0) };
+        assert!(matches!(decide(&nodes, hours(10.0), &info(120.0, 120.0), &budget), Decision::Refuse(_)));
+    }
+
+    #[test]
+    fn refuse_policy_never_deploys_on_shortfall() {
+        let nodes = [node("a", "standardLinux32gb")];
+        assert!(matches!(decide(&nodes, hours(10.0), &info(100.0, 120.0), &billing(ShortfallPolicy::Refuse, 1.0)), Decision::Refuse(_)));
+    }
+}
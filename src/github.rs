@@ -1,6 +1,12 @@
 // src/github.rs
 
-use std::process::Command;
+use crate::backend::GhBackend;
+use crate::config::NodeConfig;
+use crate::log;
+use crate::retry::{RetryConfig, RetryPolicy};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::thread;
 use std::time::{Duration, Instant};
@@ -9,6 +15,21 @@ use std::time::{Duration, Instant};
 pub enum GHError {
     CommandError(String),
     AuthError(String),
+    /// HTTP error yang tidak masuk kategori lain.
+    Http { status: u16, message: String },
+    /// Kena rate limit; `reset_at` dari header `x-ratelimit-reset` kalau ada.
+    RateLimited { reset_at: Option<DateTime<Utc>>, message: String },
+    NotFound(String),
+    /// Token tidak punya akses atau scope yang dibutuhkan (mis. `codespace`).
+    PermissionDenied(String),
+    /// Machine type tidak tersedia untuk repo/akun ini.
+    MachineUnavailable(String),
+    /// Kuota gratis habis atau spending limit tercapai.
+    QuotaExceeded(String),
+    /// Operasi tidak selesai dalam batas waktu (subprocess sudah di-kill).
+    Timeout { operation: String, after: Duration },
+    /// Response tidak bisa di-parse.
+    Parse(String),
 }
 
 impl fmt::Display for GHError {
@@ -16,156 +37,421 @@ impl fmt::Display for GHError {
         match self {
             GHError::CommandError(e) => write!(f, "Command failed: {}", e),
             GHError::AuthError(e) => write!(f, "Auth error: {}", e),
+            GHError::Http { status, message } => write!(f, "HTTP {}: {}", status, message),
+            GHError::RateLimited { reset_at: Some(reset), message } => write!(f, "Rate limited until {} UTC: {}", reset.format("%H:%M:%S"), message),
+            GHError::RateLimited { reset_at: None, message } => write!(f, "Rate limited: {}", message),
+            GHError::NotFound(e) => write!(f, "Not found: {}", e),
+            GHError::PermissionDenied(e) => write!(f, "Permission denied: {}", e),
+            GHError::MachineUnavailable(e) => write!(f, "Machine type unavailable: {}", e),
+            GHError::QuotaExceeded(e) => write!(f, "Quota exceeded: {}", e),
+            GHError::Timeout { operation, after } => write!(f, "Timeout: {} tidak selesai dalam {}s", operation, after.as_secs()),
+            GHError::Parse(e) => write!(f, "Parse error: {}", e),
         }
     }
 }
 
-fn run_gh_command(token: &str, args: &[&str]) -> Result<String, GHError> {
-    let output = Command::new("gh")
-        .args(args)
-        .env("GH_TOKEN", token)
-        .output()
-        .map_err(|e| GHError::CommandError(format!("Failed to execute gh: {}", e)))?;
+impl GHError {
+    /// Klasifikasi error HTTP dari status dan pesan GitHub. Dipakai backend
+    /// REST dan `gh` CLI (yang mencetak "HTTP <status>" di stderr, 0 kalau tidak ada).
+    /// Status menentukan dulu; isi pesan hanya dibaca untuk 403/422 (dan 0),
+    /// supaya 5xx yang kebetulan menyebut "billing" tetap di-retry.
+    pub fn from_http(status: u16, message: String, reset_at: Option<DateTime<Utc>>) -> GHError {
+        match status {
+            429 => return GHError::RateLimited { reset_at, message },
+            401 => return GHError::AuthError(format!("HTTP 401: {}", message)),
+            404 => return GHError::NotFound(message),
+            0 | 403 | 422 => {}
+            _ => return GHError::Http { status, message },
+        }
+        let lower = message.to_lowercase();
+        if lower.contains("rate limit") {
+            return GHError::RateLimited { reset_at, message };
+        }
+        if lower.contains("spending limit") || lower.contains("quota") || lower.contains("billing") {
+            return GHError::QuotaExceeded(message);
+        }
+        if lower.contains("machine") && (lower.contains("not available") || lower.contains("not allowed") || lower.contains("invalid")) {
+            return GHError::MachineUnavailable(message);
+        }
+        match status {
+            403 => GHError::PermissionDenied(message),
+            _ => GHError::Http { status, message },
+        }
+    }
 
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    
-    if !output.status.success() {
-        if stderr.contains("Bad credentials") 
-            || stderr.contains("authentication required")
-            || stderr.contains("HTTP 401") {
-            return Err(GHError::AuthError(stderr));
+    /// Error yang melekat pada akun/token ini, jadi token lain mungkin berhasil.
+    pub fn is_account_error(&self) -> bool {
+        matches!(self,
+            GHError::AuthError(_) | GHError::PermissionDenied(_) | GHError::QuotaExceeded(_)
+            | GHError::MachineUnavailable(_) | GHError::NotFound(_))
+    }
+
+    /// Error sementara yang layak dicoba ulang dengan token yang sama.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            GHError::CommandError(_) | GHError::RateLimited { .. } | GHError::Timeout { .. } => true,
+            GHError::Http { status, .. } => *status >= 500,
+            _ => false,
         }
-        
-        if stderr.contains("no codespaces found") || stdout.trim().is_empty() {
-            return Ok("".to_string());
+    }
+
+    /// Berapa lama menunggu sebelum mencoba lagi kalau ini rate limit.
+    pub fn rate_limit_wait(&self) -> Option<Duration> {
+        match self {
+            GHError::RateLimited { reset_at, .. } => Some(
+                reset_at
+                    .and_then(|reset| (reset - Utc::now()).to_std().ok())
+                    .unwrap_or(Duration::from_secs(60))
+                    + Duration::from_secs(5)
+            ),
+            _ => None,
         }
-        
-        return Err(GHError::CommandError(stderr));
     }
-    
-    Ok(stdout.trim().to_string())
 }
 
-pub fn get_username(token: &str) -> Result<String, GHError> {
-    run_gh_command(token, &["api", "user", "--jq", ".login"])
+/// Codespace seperti yang dilaporkan `gh codespace list/view --json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Codespace {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: String,
+    pub state: String,
+    #[serde(default, rename = "machineName")]
+    pub machine: String,
+    #[serde(default)]
+    pub repository: String,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub idle_timeout_minutes: Option<u32>,
 }
 
-fn stop_codespace(token: &str, name: &str) -> Result<(), GHError> {
-    println!("      Stopping '{}'...", name);
-    match run_gh_command(token, &["codespace", "stop", "-c", name]) {
-        Ok(_) => { println!("      Stopped"); thread::sleep(Duration::from_secs(5)); Ok(()) }
-        Err(e) => { eprintln!("      Warning while stopping: {}", e); thread::sleep(Duration::from_secs(3)); Ok(()) }
+impl Codespace {
+    pub fn is_available(&self) -> bool {
+        self.state == "Available"
     }
 }
 
-fn delete_codespace(token: &str, name: &str) -> Result<(), GHError> {
-    println!("      Deleting '{}'...", name);
-    for attempt in 1..=3 {
-        match run_gh_command(token, &["codespace", "delete", "-c", name, "--force"]) {
-            Ok(_) => { println!("      Deleted"); thread::sleep(Duration::from_secs(3)); return Ok(()); }
-            Err(_) => {
-                if attempt < 3 { eprintln!("      Retry {}/3", attempt); thread::sleep(Duration::from_secs(5)); } 
-                else { eprintln!("      Failed after 3 attempts, continue anyway"); return Ok(()); }
-            }
+/// Codespace yang dibuat orchestrator (dicatat di state). Stop/delete hanya
+/// boleh menyentuh codespace ini, kecuali `all` (opt-in `cleanup_all`).
+pub struct Ownership<'a> {
+    pub owned: &'a mut Vec<String>,
+    pub all: bool,
+}
+
+impl Ownership<'_> {
+    pub fn owns(&self, cs: &Codespace) -> bool {
+        self.all || self.owned.contains(&cs.name)
+    }
+
+    fn record(&mut self, name: &str) {
+        if !self.owned.iter().any(|n| n == name) {
+            self.owned.push(name.to_string());
         }
     }
-    Ok(())
+
+    pub fn forget(&mut self, name: &str) {
+        self.owned.retain(|n| n != name);
+    }
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
 }
 
-fn wait_for_deletion(token: &str, repo: &str, timeout_secs: u64) -> Result<(), GHError> {
-    println!("      Waiting for old codespaces to be fully deleted...");
+pub fn get_username(gh: &dyn GhBackend, token: &str) -> Result<String, GHError> {
+    let body = gh.api(token, "user")?;
+    let user: User = serde_json::from_str(&body)
+        .map_err(|e| GHError::Parse(format!("Unexpected /user response: {}", e)))?;
+    Ok(user.login)
+}
+
+pub fn stop_codespace(gh: &dyn GhBackend, token: &str, name: &str, policy: &RetryPolicy) -> Result<(), GHError> {
+    info!("Stopping '{}'...", name);
+    match gh.stop_codespace(token, name) {
+        Ok(_) => { info!("Stopped"); thread::sleep(policy.settle()); Ok(()) }
+        Err(e) => { warn!("Warning while stopping: {}", e); thread::sleep(policy.settle()); Ok(()) }
+    }
+}
+
+pub fn delete_codespace(gh: &dyn GhBackend, token: &str, name: &str, policy: &RetryPolicy) -> Result<(), GHError> {
+    info!("Deleting '{}'...", name);
+    match policy.run("Delete", GHError::is_retryable, |_| gh.delete_codespace(token, name)) {
+        Ok(_) => { info!("Deleted"); thread::sleep(policy.settle()); Ok(()) }
+        Err(GHError::NotFound(_)) => { info!("Already gone"); Ok(()) }
+        Err(e) => { warn!("Failed to delete ({}), continue anyway", e); Ok(()) }
+    }
+}
+
+fn wait_for_deletion(gh: &dyn GhBackend, token: &str, repo: &str, names: &[String], policy: &RetryPolicy) -> Result<(), GHError> {
+    info!("Waiting for old codespaces to be fully deleted...");
     let start_time = Instant::now();
+    let mut attempt = 1;
     loop {
-        if start_time.elapsed().as_secs() >= timeout_secs {
-            return Err(GHError::CommandError("Timeout: Old codespaces were not deleted in time.".to_string()));
-        }
-        let list_output = run_gh_command(token, &["codespace", "list", "-r", repo, "-q", "."],)?;
-        if list_output.trim().is_empty() {
-            println!("      All old codespaces confirmed deleted.");
+        if !gh.list_codespaces(token, repo)?.iter().any(|cs| names.contains(&cs.name)) {
+            info!("All old codespaces confirmed deleted.");
             return Ok(());
         }
-        println!("      Still deleting... checking again in 10s.");
-        thread::sleep(Duration::from_secs(10));
-    }
-}
-
-pub fn verify_codespace(token: &str, name: &str) -> Result<bool, GHError> {
-    let state_check = run_gh_command(token, &["codespace", "view", "-c", name, "--json", "state", "-q", ".state"]);
-    match state_check {
-        Ok(state) if state == "Available" => Ok(true),
-        _ => Ok(false),
-    }
-}
-
-pub fn wait_and_run_startup_script(token: &str, name: &str) -> Result<(), GHError> {
-    println!("   Verifying and starting node '{}'...", name);
-    for attempt in 1..=10 {
-        println!("      Attempt {}/10: Checking SSH readiness...", attempt);
-        match run_gh_command(token, &["codespace", "ssh", "-c", name, "--", "echo 'ready'"]) {
-            Ok(output) if output.contains("ready") => {
-                println!("      SSH is ready. Executing auto-start script in a login shell...");
-                let script_path = "/workspaces/mawari-nexus-blueprint/auto-start.sh";
-                let exec_command = format!("bash -l -c 'bash {}'", script_path);
-                match run_gh_command(token, &["codespace", "ssh", "-c", name, "--", &exec_command]) {
-                    Ok(start_output) => {
-                        println!("      Script execution successful.");
-                        println!("      Output snippet: {}", start_output.lines().next().unwrap_or(""));
-                        return Ok(());
-                    },
-                    Err(e) => { eprintln!("      Error executing auto-start script: {}", e); }
-                }
-            },
-            _ => { println!("      Codespace is not yet SSH-ready."); }
-        }
-        if attempt < 10 {
-            println!("      Waiting 30 seconds before next attempt...");
-            thread::sleep(Duration::from_secs(30));
-        }
-    }
-    Err(GHError::CommandError(format!("Timeout: Failed to reliably start node in '{}' after multiple attempts.", name)))
-}
-
-pub fn nuke_and_create(token: &str, repo: &str) -> Result<(String, String), GHError> {
-    println!("  Scanning existing codespaces for repo '{}'...", repo);
-    let list_output = run_gh_command(token, &["codespace", "list", "-r", repo, "--json", "name,state", "-q", ".[]"])?;
-    if !list_output.is_empty() {
-        let codespaces: Vec<&str> = list_output.lines().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-        if !codespaces.is_empty() {
-            println!("  Found {} old codespace(s), cleaning...", codespaces.len());
-            for cs_json in codespaces {
-                if let (Some(name_start), Some(name_end)) = (cs_json.find("\"name\":\""), cs_json.find("\",\"state\"")) {
-                    let name = &cs_json[name_start + 8..name_end];
-                    let state = if cs_json.contains("\"state\":\"Available\"") || cs_json.contains("\"state\":\"Running\"") { "Running" } else { "Stopped" };
-                    println!("    Codespace: {} ({})", name, state);
-                    if state == "Running" { stop_codespace(token, name)?; }
-                    delete_codespace(token, name)?;
-                }
-            }
-            println!("  Cleanup commands sent.");
-            wait_for_deletion(token, repo, 90)?;
+        if !policy.allows(attempt) {
+            return Err(GHError::Timeout {
+                operation: "waiting for old codespaces to be deleted".to_string(),
+                after: start_time.elapsed(),
+            });
+        }
+        let delay = policy.delay(attempt);
+        info!("Still deleting... checking again in {:.0}s.", delay.as_secs_f64());
+        thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// `Ok(false)` kalau codespace ada tapi belum Available, `Err(NotFound)` kalau
+/// sudah hilang. Error lain (rate limit, jaringan, auth) diteruskan apa adanya.
+pub fn verify_codespace(gh: &dyn GhBackend, token: &str, name: &str) -> Result<bool, GHError> {
+    gh.view_codespace(token, name).map(|cs| cs.is_available())
+}
+
+pub fn wait_and_run_startup_script(gh: &dyn GhBackend, token: &str, name: &str, startup_command: &str, policy: &RetryPolicy) -> Result<(), GHError> {
+    info!("Verifying and starting node '{}'...", name);
+    // Codespace yang baru dibuat bisa sebentar "not found" sebelum SSH siap.
+    let retryable = |e: &GHError| e.is_retryable() || matches!(e, GHError::NotFound(_));
+    policy.run("Startup", retryable, |attempt| {
+        info!("Attempt {}: Checking SSH readiness...", attempt);
+        let output = gh.ssh(token, name, "echo 'ready'")?;
+        if !output.contains("ready") {
+            return Err(GHError::CommandError(format!("'{}' is not yet SSH-ready", name)));
+        }
+        info!("SSH is ready. Executing startup command...");
+        let start_output = gh.ssh(token, name, startup_command)?;
+        info!("Script execution successful.");
+        info!("Output snippet: {}", start_output.lines().next().unwrap_or(""));
+        Ok(())
+    })
+}
+
+pub fn create_node(gh: &dyn GhBackend, token: &str, repo: &str, node: &NodeConfig, ownership: &mut Ownership) -> Result<String, GHError> {
+    let name = gh.create_codespace(token, repo, node)?;
+    if name.is_empty() { return Err(GHError::CommandError(format!("Failed to create {}", node.display_name()))); }
+    ownership.record(&name);
+    info!("Created {}", name);
+    Ok(name)
+}
+
+/// Stop dan hapus codespace milik orchestrator di repo (semua jika `ownership.all`),
+/// lalu tunggu sampai benar-benar hilang.
+pub fn cleanup_codespaces(gh: &dyn GhBackend, token: &str, repo: &str, ownership: &mut Ownership, retry: &RetryConfig) -> Result<(), GHError> {
+    info!("Scanning existing codespaces for repo '{}'...", repo);
+    let (codespaces, foreign): (Vec<Codespace>, Vec<Codespace>) = gh.list_codespaces(token, repo)?
+        .into_iter()
+        .partition(|cs| ownership.owns(cs));
+    if !foreign.is_empty() {
+        info!("Skipping {} codespace(s) not created by orchestrator:", foreign.len());
+        for cs in &foreign {
+            info!("Codespace: {} ({})", cs.name, cs.display_name);
+        }
+    }
+    if !codespaces.is_empty() {
+        info!("Found {} old codespace(s), cleaning...", codespaces.len());
+        let names: Vec<String> = codespaces.iter().map(|cs| cs.name.clone()).collect();
+        for cs in &codespaces {
+            info!("Codespace: {} ({})", cs.name, cs.state);
+            if cs.is_available() { stop_codespace(gh, token, &cs.name, &retry.delete)?; }
+            delete_codespace(gh, token, &cs.name, &retry.delete)?;
+        }
+        info!("Cleanup commands sent.");
+        wait_for_deletion(gh, token, repo, &names, &retry.deletion_wait)?;
+        for name in &names {
+            ownership.forget(name);
         }
     } else {
-        println!("  No old codespaces found for this repo.");
+        info!("No old codespaces found for this repo.");
     }
+    Ok(())
+}
+
+/// Hapus codespace milik orchestrator di repo lalu buat satu codespace per node.
+/// Return map nama node -> nama codespace.
+pub fn nuke_and_create(gh: &dyn GhBackend, token: &str, repo: &str, nodes: &[NodeConfig], ownership: &mut Ownership, retry: &RetryConfig) -> Result<BTreeMap<String, String>, GHError> {
+    cleanup_codespaces(gh, token, repo, ownership, retry)?;
     
-    println!("\n  Creating new codespaces...");
-    println!("    [1/2] Creating mawari-node (basicLinux32gb)...");
-    let mawari_name = run_gh_command(token, &[ "codespace", "create", "-r", repo, "-m", "basicLinux32gb", "--display-name", "mawari-node", "--idle-timeout", "240m"])?;
-    if mawari_name.is_empty() { return Err(GHError::CommandError("Failed to create mawari-node".to_string())); }
-    println!("       Mawari: {}", mawari_name);
-    
-    println!("    [2/2] Creating nexus-node (standardLinux32gb)...");
-    let nexus_name = run_gh_command(token, &["codespace", "create", "-r", repo, "-m", "standardLinux32gb", "--display-name", "nexus-node", "--idle-timeout", "240m"])?;
-    if nexus_name.is_empty() { return Err(GHError::CommandError("Failed to create nexus-node".to_string())); }
-    println!("       Nexus: {}", nexus_name);
+    info!("Creating new codespaces...");
+    let mut created = BTreeMap::new();
+    for (idx, node) in nodes.iter().enumerate() {
+        let _node = log::node(&node.name);
+        info!("[{}/{}] Creating {} ({})...", idx + 1, nodes.len(), node.display_name(), node.machine);
+        let name = create_node(gh, token, repo, node, ownership)?;
+        created.insert(node.name.clone(), name);
+    }
     
-    println!("\n  Starting nodes via direct script execution...");
-    wait_and_run_startup_script(token, &mawari_name)?;
-    wait_and_run_startup_script(token, &nexus_name)?;
-    Ok((mawari_name, nexus_name))
+    info!("Starting nodes via direct script execution...");
+    for node in nodes {
+        let _node = log::node(&node.name);
+        wait_and_run_startup_script(gh, token, &created[&node.name], &node.startup_command, &retry.startup)?;
+    }
+    Ok(created)
 }
 
-pub fn ssh_command(token: &str, codespace_name: &str, cmd: &str) -> Result<String, GHError> {
-    run_gh_command(token, &["codespace", "ssh", "-c", codespace_name, "--", cmd])
+pub fn ssh_command(gh: &dyn GhBackend, token: &str, codespace_name: &str, cmd: &str) -> Result<String, GHError> {
+    gh.ssh(token, codespace_name, cmd)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::backend::FakeBackend;
+
+    pub const TOKEN: &str = "ghp_test000001";
+    pub const REPO: &str = "owner/repo";
+
+    pub fn instant(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy { max_attempts, initial_delay_secs: 0.0, max_delay_secs: 0.0, multiplier: 1.0, jitter: 0.0 }
+    }
+
+    pub fn instant_retry() -> RetryConfig {
+        RetryConfig { delete: instant(3), deletion_wait: instant(3), startup: instant(3), deploy: instant(3) }
+    }
+
+    pub fn node(name: &str, machine: &str) -> NodeConfig {
+        NodeConfig {
+            name: name.to_string(),
+            display_name: None,
+            machine: machine.to_string(),
+            cores: None,
+            idle_timeout_minutes: 30,
+            devcontainer_path: None,
+            startup_command: "start.sh".to_string(),
+        }
+    }
+
+    pub fn nodes() -> Vec<NodeConfig> {
+        vec![node("mawari", "basicLinux32gb"), node("nexus", "standardLinux32gb")]
+    }
+
+    #[test]
+    fn from_http_classifies_by_status_before_message() {
+        let http = |status: u16, message: &str| GHError::from_http(status, message.to_string(), None);
+        assert!(matches!(http(502, "billing service unavailable"), GHError::Http { status: 502, .. }));
+        assert!(http(502, "billing service unavailable").is_retryable());
+        assert!(matches!(http(404, "billing account not found"), GHError::NotFound(_)));
+        assert!(matches!(http(429, "slow down"), GHError::RateLimited { .. }));
+        assert!(matches!(http(403, "You have exceeded a secondary rate limit"), GHError::RateLimited { .. }));
+        assert!(matches!(http(403, "Spending limit reached"), GHError::QuotaExceeded(_)));
+        assert!(matches!(http(422, "Machine type not available for this repository"), GHError::MachineUnavailable(_)));
+        assert!(matches!(http(403, "Resource not accessible by integration"), GHError::PermissionDenied(_)));
+        assert!(matches!(http(0, "codespace quota reached"), GHError::QuotaExceeded(_)));
+    }
+
+    fn names(gh: &FakeBackend) -> Vec<String> {
+        gh.list_codespaces(TOKEN, REPO).unwrap().into_iter().map(|cs| cs.name).collect()
+    }
+
+    #[test]
+    fn nuke_and_create_on_empty_repo_creates_every_node() {
+        let gh = FakeBackend::default();
+        let mut owned = Vec::new();
+        let mut ownership = Ownership { owned: &mut owned, all: false };
+        let created = nuke_and_create(&gh, TOKEN, REPO, &nodes(), &mut ownership, &instant_retry()).unwrap();
+
+        assert_eq!(created.len(), 2);
+        let mut listed = names(&gh);
+        listed.sort();
+        let mut expected: Vec<String> = created.values().cloned().collect();
+        expected.sort();
+        assert_eq!(listed, expected);
+        assert_eq!(owned.len(), 2);
+    }
+
+    #[test]
+    fn nuke_and_create_replaces_only_owned_codespaces() {
+        let gh = FakeBackend::default();
+        let foreign = gh.create_codespace(TOKEN, REPO, &node("manual", "basicLinux32gb")).unwrap();
+        let mut owned = Vec::new();
+        let first = {
+            let mut ownership = Ownership { owned: &mut owned, all: false };
+            nuke_and_create(&gh, TOKEN, REPO, &nodes(), &mut ownership, &instant_retry()).unwrap()
+        };
+        let second = {
+            let mut ownership = Ownership { owned: &mut owned, all: false };
+            nuke_and_create(&gh, TOKEN, REPO, &nodes(), &mut ownership, &instant_retry()).unwrap()
+        };
+
+        let listed = names(&gh);
+        assert!(listed.contains(&foreign));
+        for name in first.values() {
+            assert!(!listed.contains(name));
+            assert!(!owned.contains(name));
+        }
+        for name in second.values() {
+            assert!(listed.contains(name));
+            assert!(owned.contains(name));
+        }
+        assert_eq!(listed.len(), 3);
+    }
+
+    #[test]
+    fn cleanup_all_also_removes_foreign_codespaces() {
+        let gh = FakeBackend::default();
+        gh.create_codespace(TOKEN, REPO, &node("manual", "basicLinux32gb")).unwrap();
+        gh.create_codespace(TOKEN, "other/repo", &node("elsewhere", "basicLinux32gb")).unwrap();
+        let mut owned = Vec::new();
+        let mut ownership = Ownership { owned: &mut owned, all: true };
+        cleanup_codespaces(&gh, TOKEN, REPO, &mut ownership, &instant_retry()).unwrap();
+
+        assert!(names(&gh).is_empty());
+        assert_eq!(gh.list_codespaces(TOKEN, "other/repo").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn cleanup_without_ownership_keeps_foreign_codespaces() {
+        let gh = FakeBackend::default();
+        let foreign = gh.create_codespace(TOKEN, REPO, &node("manual", "basicLinux32gb")).unwrap();
+        let mut owned = Vec::new();
+        let mut ownership = Ownership { owned: &mut owned, all: false };
+        cleanup_codespaces(&gh, TOKEN, REPO, &mut ownership, &instant_retry()).unwrap();
+
+        assert_eq!(names(&gh), vec![foreign]);
+    }
+
+    #[test]
+    fn wait_for_deletion_times_out_when_codespace_stays() {
+        let gh = FakeBackend::default();
+        let name = gh.create_codespace(TOKEN, REPO, &node("stuck", "basicLinux32gb")).unwrap();
+        let result = wait_for_deletion(&gh, TOKEN, REPO, &[name], &instant(2));
+        assert!(matches!(result, Err(GHError::Timeout { .. })));
+    }
+
+    #[test]
+    fn wait_for_deletion_returns_once_gone() {
+        let gh = FakeBackend::default();
+        assert!(wait_for_deletion(&gh, TOKEN, REPO, &["gone".to_string()], &instant(1)).is_ok());
+    }
+
+    #[test]
+    fn delete_treats_missing_codespace_as_done() {
+        let gh = FakeBackend::default();
+        assert!(delete_codespace(&gh, TOKEN, "missing", &instant(3)).is_ok());
+    }
+
+    #[test]
+    fn verify_distinguishes_stopped_from_missing() {
+        let gh = FakeBackend::default();
+        let name = gh.create_codespace(TOKEN, REPO, &node("mawari", "basicLinux32gb")).unwrap();
+        assert!(verify_codespace(&gh, TOKEN, &name).unwrap());
+        gh.stop_codespace(TOKEN, &name).unwrap();
+        assert!(!verify_codespace(&gh, TOKEN, &name).unwrap());
+        assert!(matches!(verify_codespace(&gh, TOKEN, "missing"), Err(GHError::NotFound(_))));
+    }
+
+    #[test]
+    fn startup_gives_up_on_stopped_codespace() {
+        let gh = FakeBackend::default();
+        let name = gh.create_codespace(TOKEN, REPO, &node("mawari", "basicLinux32gb")).unwrap();
+        gh.stop_codespace(TOKEN, &name).unwrap();
+        let result = wait_and_run_startup_script(&gh, TOKEN, &name, "start.sh", &instant(2));
+        assert!(matches!(result, Err(GHError::CommandError(_))));
+    }
 }
@@ -0,0 +1,82 @@
+// src/guard.rs
+
+use crate::billing::{BillingConfig, BillingInfo};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Object `billing.guard` di config.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct GuardConfig {
+    pub enabled: bool,
+    /// Persen kuota compute gratis yang boleh dicapai proyeksi usage sampai
+    /// akhir cycle. `max_budget_usd` (kalau ada) ditambahkan di atasnya.
+    pub threshold_percent: f64,
+}
+
+impl Default for GuardConfig {
+    fn default() -> Self {
+        GuardConfig { enabled: true, threshold_percent: 100.0 }
+    }
+}
+
+impl GuardConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.threshold_percent.is_finite() || self.threshold_percent <= 0.0 {
+            return Err("billing.guard.threshold_percent harus angka > 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Proyeksikan usage sampai deadline: usage sekarang + `cores` x sisa waktu.
+/// `hourly` = USD per jam node yang jalan, untuk mengonversi budget ke core-hours.
+/// Err berisi alasan kalau proyeksi melewati batas.
+pub fn check(billing: &BillingConfig, cores: u32, hourly: Option<f64>, remaining: Duration, info: &BillingInfo) -> Result<(), String> {
+    let hours = remaining.as_secs_f64() / 3600.0;
+    let used = info.total_core_hours_used as f64;
+    let projected = used + cores as f64 * hours;
+    let budget = match (billing.max_budget_usd, hourly) {
+        (Some(usd), Some(hourly)) if hourly > 0.0 => usd / (hourly / cores.max(1) as f64),
+        _ => 0.0,
+    };
+    let limit = info.included_core_hours as f64 * billing.guard.threshold_percent / 100.0 + budget;
+    info!("Spend guard: {:.1} + {} core x {:.1}h = {:.1} core-hours, batas {:.1}", used, cores, hours, projected, limit);
+    if projected > limit {
+        return Err(format!(
+            "Proyeksi {:.1} core-hours sampai akhir cycle melewati batas {:.1} ({}% dari {:.0} gratis{})",
+            projected, limit, billing.guard.threshold_percent, info.included_core_hours,
+            if budget > 0.0 { format!(" + budget {:.1}", budget) } else { String::new() }
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::billing::tests::{hours, info};
+
+    #[test]
+    fn trips_when_projection_passes_free_allowance() {
+        let billing = BillingConfig::default();
+        assert!(check(&billing, 6, Some(0.54), hours(3.0), &info(100.0, 120.0)).is_ok());
+        assert!(check(&billing, 6, Some(0.54), hours(5.0), &info(100.0, 120.0)).is_err());
+    }
+
+    #[test]
+    fn threshold_scales_free_allowance() {
+        let mut billing = BillingConfig::default();
+        billing.guard.threshold_percent = 50.0;
+        assert!(check(&billing, 2, None, hours(1.0), &info(59.0, 120.0)).is_err());
+    }
+
+    #[test]
+    fn budget_uses_rate_of_deployed_machines() {
+        let billing = BillingConfig { max_budget_usd: Some(0.9), ..Default::default() };
+        // Dua node 2-core setelah downgrade: $0.36/jam untuk 4 core = $0.09 per core-hour,
+        // jadi budget $0.90 = 10 core-hours di atas kuota gratis.
+        assert!(check(&billing, 4, Some(0.36), hours(2.0), &info(120.0, 120.0)).is_ok());
+        assert!(check(&billing, 4, Some(0.36), hours(3.0), &info(120.0, 120.0)).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+// src/history.rs
+
+use crate::billing::{BillingInfo, BillingPeriod};
+use crate::config;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Snapshot lebih tua dari ini dibuang saat menyimpan.
+const KEEP_DAYS: i64 = 100;
+
+/// Usage satu akun di satu hari (nilai terakhir hari itu).
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Snapshot {
+    pub core_hours: f32,
+    pub storage_gb_months: f32,
+}
+
+/// Key = username, lalu tanggal UTC.
+#[derive(Serialize, Deserialize, Default)]
+pub struct History {
+    pub accounts: BTreeMap<String, BTreeMap<NaiveDate, Snapshot>>,
+}
+
+impl History {
+    pub fn record(&mut self, username: &str, date: NaiveDate, info: &BillingInfo) {
+        let days = self.accounts.entry(username.to_string()).or_default();
+        days.insert(date, Snapshot {
+            core_hours: info.total_core_hours_used,
+            storage_gb_months: info.storage_gb_months_used,
+        });
+        days.retain(|day, _| (date - *day).num_days() <= KEEP_DAYS);
+    }
+
+    /// Snapshot akun di dalam `period`, urut tanggal.
+    pub fn in_period(&self, username: &str, period: &BillingPeriod) -> Vec<(NaiveDate, Snapshot)> {
+        self.accounts.get(username)
+            .map(|days| days.iter().filter(|(d, _)| period.contains(**d)).map(|(d, s)| (*d, *s)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Core-hours per hari di periode ini. Dari selisih snapshot pertama dan
+    /// terakhir kalau ada minimal dua hari, kalau tidak rata-rata sejak awal periode.
+    pub fn trend(&self, username: &str, info: &BillingInfo, today: NaiveDate) -> f32 {
+        let snapshots = self.in_period(username, &info.period);
+        if let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) {
+            let days = (last.0 - first.0).num_days();
+            if days > 0 {
+                return ((last.1.core_hours - first.1.core_hours) / days as f32).max(0.0);
+            }
+        }
+        info.total_core_hours_used / info.period.days_elapsed(today) as f32
+    }
+}
+
+/// File belum ada atau rusak = history kosong; history hanya untuk informasi.
+pub fn load_history(path: &str) -> History {
+    if !Path::new(path).exists() {
+        return History::default();
+    }
+    match fs::read_to_string(path).map(|data| serde_json::from_str(&data)) {
+        Ok(Ok(history)) => history,
+        _ => {
+            warn!("{} tidak bisa dibaca, history dimulai ulang", path);
+            History::default()
+        }
+    }
+}
+
+pub fn save_history(path: &str, history: &History) -> io::Result<()> {
+    config::write_atomic(path, &serde_json::to_string_pretty(history)?)
+}
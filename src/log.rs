@@ -0,0 +1,208 @@
+// src/log.rs
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// Jumlah file lama yang disimpan saat rotasi: `<path>.1` sampai `<path>.3`.
+const KEEP_FILES: u32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn parse(value: &str) -> Result<Level, String> {
+        match value {
+            "debug" => Ok(Level::Debug),
+            "info" => Ok(Level::Info),
+            "warn" => Ok(Level::Warn),
+            "error" => Ok(Level::Error),
+            other => Err(format!("Log level '{}' tidak dikenal (pilih: debug, info, warn, error)", other)),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Result<Format, String> {
+        match value {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            other => Err(format!("Log format '{}' tidak dikenal (pilih: text, json)", other)),
+        }
+    }
+}
+
+/// File log yang dirotasi kalau ukurannya lewat `max_bytes`.
+struct LogFile {
+    path: String,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+}
+
+impl LogFile {
+    fn open(path: &str, max_bytes: u64) -> io::Result<LogFile> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(LogFile { path: path.to_string(), file, size, max_bytes })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 + 1 > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    /// `<path>` -> `<path>.1` -> ... -> `<path>.KEEP_FILES`, yang paling lama dibuang.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for n in (1..KEEP_FILES).rev() {
+            let from = format!("{}.{}", self.path, n);
+            if fs::metadata(&from).is_ok() {
+                fs::rename(&from, format!("{}.{}", self.path, n + 1))?;
+            }
+        }
+        fs::rename(&self.path, format!("{}.1", self.path))?;
+        *self = LogFile::open(&self.path, self.max_bytes)?;
+        Ok(())
+    }
+}
+
+struct Logger {
+    level: Level,
+    format: Format,
+    file: Option<LogFile>,
+    /// Konteks yang ikut di setiap baris: akun aktif dan node yang sedang diproses.
+    account: Option<String>,
+    node: Option<String>,
+}
+
+static LOGGER: Mutex<Logger> = Mutex::new(Logger {
+    level: Level::Info,
+    format: Format::Text,
+    file: None,
+    account: None,
+    node: None,
+});
+
+#[derive(Serialize)]
+struct Record<'a> {
+    ts: String,
+    level: Level,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    node: Option<&'a str>,
+    msg: &'a str,
+}
+
+impl Record<'_> {
+    fn render(&self, format: Format) -> String {
+        match format {
+            Format::Json => serde_json::to_string(self).unwrap_or_default(),
+            Format::Text => {
+                let context = match (self.account, self.node) {
+                    (Some(account), Some(node)) => format!("[{} {}] ", account, node),
+                    (Some(ctx), None) | (None, Some(ctx)) => format!("[{}] ", ctx),
+                    (None, None) => String::new(),
+                };
+                format!("{} {:<5} {}{}", self.ts, self.level.label(), context, self.msg)
+            }
+        }
+    }
+}
+
+/// Atur level, format dan file log. Dipanggil sekali di awal `main`.
+pub fn init(level: Level, format: Format, file: Option<&str>, max_bytes: u64) -> io::Result<()> {
+    let file = file.map(|path| LogFile::open(path, max_bytes)).transpose()?;
+    let mut logger = LOGGER.lock().unwrap();
+    logger.level = level;
+    logger.format = format;
+    logger.file = file;
+    Ok(())
+}
+
+/// Akun yang sedang dipakai, mis. `#1 @user`. `None` saat ganti token.
+pub fn set_account(account: Option<String>) {
+    LOGGER.lock().unwrap().account = account;
+}
+
+/// Konteks node selama nilai ini hidup; konteks sebelumnya dikembalikan saat drop.
+pub struct NodeScope(Option<String>);
+
+pub fn node(name: &str) -> NodeScope {
+    let mut logger = LOGGER.lock().unwrap();
+    NodeScope(logger.node.replace(name.to_string()))
+}
+
+impl Drop for NodeScope {
+    fn drop(&mut self) {
+        LOGGER.lock().unwrap().node = self.0.take();
+    }
+}
+
+pub fn log(level: Level, args: fmt::Arguments) {
+    let mut logger = LOGGER.lock().unwrap();
+    if level < logger.level {
+        return;
+    }
+    let msg = args.to_string();
+    let record = Record {
+        ts: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        level,
+        account: logger.account.as_deref(),
+        node: logger.node.as_deref(),
+        msg: &msg,
+    };
+    let line = record.render(logger.format);
+    // Semua level ke stderr; stdout hanya untuk output laporan (mis. `billing --json`).
+    eprintln!("{}", line);
+    if let Some(file) = logger.file.as_mut() {
+        if let Err(e) = file.write_line(&line) {
+            eprintln!("WARNING: Gagal menulis log {}: {}", file.path, e);
+        }
+    }
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::log::log($crate::log::Level::Debug, format_args!($($arg)*)) };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::log::log($crate::log::Level::Info, format_args!($($arg)*)) };
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => { $crate::log::log($crate::log::Level::Warn, format_args!($($arg)*)) };
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => { $crate::log::log($crate::log::Level::Error, format_args!($($arg)*)) };
+}
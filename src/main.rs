@@ -1,280 +1,1016 @@
 // src/main.rs
 
+#[macro_use]
+mod log;
+mod backend;
+mod cli;
 mod config;
+mod forecast;
 mod github;
+mod guard;
+mod history;
+mod reconcile;
+mod retry;
 mod billing;
+mod cache;
+mod clock;
+mod pricing;
 
-use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
 use std::env;
+use std::process;
+use backend::GhBackend;
+use cli::{Command, GlobalOpts};
 
-const STATE_FILE: &str = "state.json";
-const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(3 * 3600 + 30 * 60); // 3.5 jam
-
-// ... (fungsi show_status, verify_current, dan restart_nodes tidak berubah) ...
-fn show_status() {
+fn show_status(opts: &GlobalOpts) -> i32 {
     println!("STATUS ORCHESTRATOR");
     println!("==========================================");
+    let mut code = cli::exit::OK;
     
-    match config::load_state(STATE_FILE) {
+    match config::load_state(&opts.state_path) {
         Ok(state) => {
             println!("State file found");
             println!("Current Token Index: {}", state.current_account_index);
-            if !state.current_mawari_name.is_empty() {
-                println!("Mawari Node: {}", state.current_mawari_name);
+            for (node, codespace) in &state.nodes {
+                println!("Node {}: {}", node, codespace);
             }
-            if !state.current_nexus_name.is_empty() {
-                println!("Nexus Node: {}", state.current_nexus_name);
+            if let Some(paused) = state.guard_paused_at {
+                println!("Spend guard: node di-stop sejak {} UTC, pause sampai akhir cycle", paused.format("%Y-%m-%d %H:%M"));
             }
         }
-        Err(_) => {
-            println!("No state file found");
+        Err(e) => {
+            eprintln!("Error loading state: {}", e);
+            code = cli::exit::CONFIG;
         }
     }
     
     println!("\nTokens Available:");
-    match config::load_config("tokens.json") {
+    match config::load_config(&opts.config_path) {
         Ok(cfg) => {
             println!("   Total: {} tokens", cfg.tokens.len());
+            let cache = cache::load_cache(&cfg.billing.cache_path);
+            if !cache.accounts.is_empty() {
+                println!("\nBilling (cache):");
+            }
+            for (username, usage) in &cache.accounts {
+                let owner = billing::BillingOwner::from_key(username);
+                billing::display_billing(&billing::evaluate(usage, &cfg.billing.allowance_for(username)), &owner);
+            }
         }
         Err(e) => {
             eprintln!("   Error loading tokens: {}", e);
+            code = cli::exit::CONFIG;
         }
     }
+    code
 }
 
-fn verify_current() {
+/// Load config dan state, pastikan token index di state masih valid.
+fn load_current(opts: &GlobalOpts) -> Result<(config::Config, config::State), i32> {
+    let state = config::load_state(&opts.state_path).map_err(|e| {
+        eprintln!("Error loading state: {}", e);
+        cli::exit::CONFIG
+    })?;
+    let config = config::load_config(&opts.config_path).map_err(|e| {
+        eprintln!("Error loading tokens: {}", e);
+        cli::exit::CONFIG
+    })?;
+    if state.current_account_index >= config.tokens.len() {
+        eprintln!("Invalid token index");
+        return Err(cli::exit::CONFIG);
+    }
+    Ok((config, state))
+}
+
+fn verify_current(gh: &dyn GhBackend, opts: &GlobalOpts) -> i32 {
     println!("VERIFIKASI NODE AKTIF");
     println!("==========================================");
     
-    let state = match config::load_state(STATE_FILE) {
-        Ok(s) => s,
-        Err(_) => {
-            eprintln!("No state file found");
-            return;
-        }
+    let (config, state) = match load_current(opts) {
+        Ok(loaded) => loaded,
+        Err(code) => return code,
     };
     
-    let config = match config::load_config("tokens.json") {
+    let token = &config.tokens[state.current_account_index];
+    
+    println!("Token Index: {}", state.current_account_index);
+    
+    let mut code = cli::exit::OK;
+    match github::get_username(gh, token) {
+        Ok(username) => {
+            println!();
+            let owner = billing::BillingOwner::User(username);
+            let status = check_billing(gh, opts, &config, token, &owner, opts.refresh);
+            billing::display_billing(&status, &owner);
+        }
+        Err(e) => {
+            eprintln!("\nBilling: {}", e);
+            code = cli::exit::FAILURE;
+        }
+    }
+
+    for (node, codespace) in &state.nodes {
+        println!("\nVerifying {}: {}", node, codespace);
+        match github::verify_codespace(gh, token, codespace) {
+            Ok(true) => println!("   RUNNING & READY"),
+            Ok(false) => {
+                println!("   NOT READY or STOPPED");
+                code = cli::exit::FAILURE;
+            }
+            Err(github::GHError::NotFound(_)) => {
+                println!("   NOT FOUND (sudah dihapus)");
+                code = cli::exit::FAILURE;
+            }
+            Err(e) => {
+                eprintln!("   Error: {}", e);
+                code = cli::exit::FAILURE;
+            }
+        }
+    }
+    code
+}
+
+fn show_billing(gh: &dyn GhBackend, opts: &GlobalOpts, json: bool, repo: Option<&str>) -> i32 {
+    let config = match config::load_config(&opts.config_path) {
         Ok(c) => c,
         Err(e) => {
             eprintln!("Error loading tokens: {}", e);
-            return;
+            return cli::exit::CONFIG;
         }
     };
     
-    if state.current_account_index >= config.tokens.len() {
-        eprintln!("Invalid token index");
-        return;
+    let mut reports = Vec::new();
+    for (idx, token) in config.tokens.iter().enumerate() {
+        let username = match github::get_username(gh, token) {
+            Ok(u) => u,
+            Err(e) => {
+                reports.push(billing::AccountReport::error(idx + 1, e.to_string()));
+                continue;
+            }
+        };
+        let owner = match repo {
+            Some(repo) => billing::detect_owner(gh, token, repo, &username, &config.billing),
+            None => billing::BillingOwner::User(username.clone()),
+        };
+        if let Some(reason) = config.billing.unfunded(&owner) {
+            warn!("{}", reason);
+        }
+        let status = check_billing(gh, opts, &config, token, &owner, opts.refresh);
+        let per_day = match &status {
+            billing::QuotaStatus::Ok(info) | billing::QuotaStatus::Exhausted(info) => {
+                let history = record_usage(opts, &config.billing.history_path, &owner.key(), info);
+                Some(history.trend(&owner.key(), info, Utc::now().date_naive()))
+            }
+            billing::QuotaStatus::Unknown(_) => None,
+        };
+        reports.push(billing::AccountReport::new(idx + 1, &username, &owner, &status, per_day, &config.billing.prices));
     }
-    
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+    } else {
+        billing::print_table(&reports);
+    }
+    if reports.iter().any(|r| r.status == "unknown" || r.status == "error") {
+        cli::exit::FAILURE
+    } else {
+        cli::exit::OK
+    }
+}
+
+fn cleanup(gh: &dyn GhBackend, opts: &GlobalOpts, repo: &str, all: bool) -> i32 {
+    let (config, mut state) = match load_current(opts) {
+        Ok(loaded) => loaded,
+        Err(code) => return code,
+    };
     let token = &config.tokens[state.current_account_index];
     
-    println!("Token Index: {}", state.current_account_index);
+    let mut ownership = github::Ownership {
+        owned: &mut state.owned_codespaces,
+        all: all || config.cleanup_all,
+    };
+    let result = github::cleanup_codespaces(gh, token, repo, &mut ownership, &config.retry);
+    let owned = &state.owned_codespaces;
+    state.nodes.retain(|_, codespace| owned.contains(codespace));
+    persist_state(opts, &state);
+    match result {
+        Ok(()) => cli::exit::OK,
+        Err(e) => {
+            error!("Cleanup failed: {}", e);
+            cli::exit::FAILURE
+        }
+    }
+}
+
+fn exec_nodes(gh: &dyn GhBackend, opts: &GlobalOpts, node: Option<&str>, command: &str) -> i32 {
+    let (config, state) = match load_current(opts) {
+        Ok(loaded) => loaded,
+        Err(code) => return code,
+    };
+    let token = &config.tokens[state.current_account_index];
     
-    if !state.current_mawari_name.is_empty() {
-        println!("\nVerifying Mawari: {}", state.current_mawari_name);
-        match github::verify_codespace(token, &state.current_mawari_name) {
-            Ok(true) => println!("   RUNNING & READY"),
-            Ok(false) => println!("   NOT READY or STOPPED"),
-            Err(e) => eprintln!("   Error: {}", e),
+    if let Some(name) = node {
+        if !state.nodes.contains_key(name) {
+            eprintln!("Node '{}' tidak ada di state", name);
+            return cli::exit::USAGE;
         }
     }
     
-    if !state.current_nexus_name.is_empty() {
-        println!("\nVerifying Nexus: {}", state.current_nexus_name);
-        match github::verify_codespace(token, &state.current_nexus_name) {
-            Ok(true) => println!("   RUNNING & READY"),
-            Ok(false) => println!("   NOT READY or STOPPED"),
-            Err(e) => eprintln!("   Error: {}", e),
+    let mut code = cli::exit::OK;
+    for (name, codespace) in &state.nodes {
+        if node.is_some_and(|n| n != name) {
+            continue;
+        }
+        println!("== {}: {}", name, codespace);
+        match github::ssh_command(gh, token, codespace, command) {
+            Ok(output) => println!("{}", output),
+            Err(e) => {
+                eprintln!("   Error: {}", e);
+                code = cli::exit::FAILURE;
+            }
         }
     }
+    code
 }
 
-fn restart_nodes(token: &str, mawari_name: &str, nexus_name: &str) {
-    let script_path = "/workspaces/mawari-nexus-blueprint/auto-start.sh";
-    let cmd = format!("bash -l -c 'bash {}'", script_path);
+fn restart_nodes(gh: &dyn GhBackend, clock: &dyn clock::Clock, token: &str, nodes: &[config::NodeConfig], deployed: &BTreeMap<String, String>) {
+    for (idx, node) in nodes.iter().enumerate() {
+        let Some(codespace) = deployed.get(&node.name) else { continue };
+        if idx > 0 {
+            clock.sleep(Duration::from_secs(2));
+        }
+        let _node = log::node(&node.name);
+        info!("Restarting {}", codespace);
+        match github::ssh_command(gh, token, codespace, &node.startup_command) {
+            Ok(output) => info!("Restart sent. Output: {}", output.lines().next().unwrap_or("")),
+            Err(e) => warn!("Restart gagal: {}", e),
+        }
+    }
+}
 
-    println!("  Restarting Mawari: {}", mawari_name);
-    match github::ssh_command(token, mawari_name, &cmd) {
-        Ok(output) => println!("    Restart sent. Output: {}", output.lines().next().unwrap_or("")),
-        Err(e) => eprintln!("    Warning: {}", e),
+/// Cycle sebelumnya belum lewat deadline: pakai lagi codespace yang tercatat,
+/// supaya restart tidak menghapus node yang sehat. State dinilai seperti
+/// reconcile: yang sedang naik ditunggu, yang stopped di-start, redeploy hanya
+/// kalau sudah hilang, state-nya rusak, atau gagal di-start. Error lain dicoba
+/// ulang lewat `policy`, lalu cycle tetap dilanjutkan.
+fn resume_cycle(gh: &dyn GhBackend, token: &str, nodes: &[config::NodeConfig], state: &config::State, policy: &retry::RetryPolicy, now: DateTime<Utc>) -> Option<BTreeMap<String, String>> {
+    let deadline = state.cycle_deadline?;
+    if deadline <= now || state.cycle_started_at.is_none() {
+        return None;
     }
-    
-    thread::sleep(Duration::from_secs(2));
-    
-    println!("  Restarting Nexus: {}", nexus_name);
-    match github::ssh_command(token, nexus_name, &cmd) {
-        Ok(output) => println!("    Restart sent. Output: {}", output.lines().next().unwrap_or("")),
-        Err(e) => eprintln!("    Warning: {}", e),
+    if state.guard_paused_at.is_some() {
+        info!("Resuming cycle (deadline {} UTC), paused oleh spend guard", deadline.format("%Y-%m-%d %H:%M"));
+        return Some(state.nodes.clone());
+    }
+    info!("Resuming cycle (deadline {} UTC), verifying nodes...", deadline.format("%Y-%m-%d %H:%M"));
+    for node in nodes {
+        let _node = log::node(&node.name);
+        let Some(codespace) = state.nodes.get(&node.name) else {
+            info!("Not recorded, redeploying");
+            return None;
+        };
+        let cs = match policy.run("Verify", github::GHError::is_retryable, |_| gh.view_codespace(token, codespace)) {
+            Ok(cs) => cs,
+            Err(github::GHError::NotFound(_)) => {
+                info!("{} sudah tidak ada, redeploying", codespace);
+                return None;
+            }
+            Err(e) => {
+                warn!("{} tidak bisa diverifikasi ({}), cycle tetap dilanjutkan", codespace, e);
+                continue;
+            }
+        };
+        match reconcile::classify(node, cs) {
+            reconcile::NodeAction::Keep(cs) if cs.is_available() => info!("{} RUNNING & READY", cs.name),
+            reconcile::NodeAction::Keep(cs) => {
+                info!("{} masih {}, menunggu sampai siap", cs.name, cs.state);
+                if let Err(e) = github::wait_and_run_startup_script(gh, token, &cs.name, &node.startup_command, policy) {
+                    warn!("{} belum siap ({}), dicoba lagi di keep-alive berikutnya", cs.name, e);
+                }
+            }
+            reconcile::NodeAction::Start(cs) => {
+                info!("{} {}, starting", cs.name, cs.state);
+                let started = reconcile::start_codespace(gh, token, &cs.name, policy)
+                    .and_then(|_| github::wait_and_run_startup_script(gh, token, &cs.name, &node.startup_command, policy));
+                if let Err(e) = started {
+                    warn!("{} gagal di-start ({}), redeploying", cs.name, e);
+                    return None;
+                }
+            }
+            reconcile::NodeAction::Recreate(cs) => {
+                info!("{} {} ({}), redeploying", cs.name, cs.state, cs.machine);
+                return None;
+            }
+            reconcile::NodeAction::Create => return None,
+        }
+    }
+    Some(state.nodes.clone())
+}
+
+/// `from + duration`; durasi yang tidak muat di `DateTime` dipotong, bukan panic.
+fn after(from: DateTime<Utc>, duration: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(duration).ok()
+        .and_then(|d| from.checked_add_signed(d))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Cek billing lewat cache kecuali `refresh` (`--refresh`). Hasil `Unknown` tidak di-cache.
+fn check_billing(gh: &dyn GhBackend, opts: &GlobalOpts, config: &config::Config, token: &str, owner: &billing::BillingOwner, refresh: bool) -> billing::QuotaStatus {
+    let key = owner.key();
+    let allowance = config.billing.allowance_for(&key);
+    let mut cache = cache::load_cache(&config.billing.cache_path);
+    if !refresh {
+        if let Some(usage) = cache.fresh(&key, config.billing.cache_ttl()) {
+            return billing::evaluate(usage, &allowance);
+        }
+    }
+    let usage = match billing::fetch_usage(gh, token, owner) {
+        Ok(usage) => usage,
+        Err(reason) => return billing::QuotaStatus::Unknown(reason),
+    };
+    cache.store(&key, usage);
+    if !opts.dry_run {
+        if let Err(e) = cache::save_cache(&config.billing.cache_path, &cache) {
+            warn!("Gagal menyimpan {}: {}", config.billing.cache_path, e);
+        }
     }
+    billing::evaluate(&usage, &allowance)
 }
 
+/// Cek ulang usage di tengah cycle. `Some(alasan)` kalau proyeksi sampai
+/// akhir cycle (`remaining`) melewati batas `billing.guard`; billing yang tidak terbaca tidak menghentikan node.
+fn spend_guard(gh: &dyn GhBackend, opts: &GlobalOpts, config: &config::Config, token: &str, repo: &str, state: &config::State, remaining: Duration) -> Option<String> {
+    let username = match github::get_username(gh, token) {
+        Ok(u) => u,
+        Err(e) => {
+            warn!("Spend guard dilewati: {}", e);
+            return None;
+        }
+    };
+    let owner = billing::detect_owner(gh, token, repo, &username, &config.billing);
+    // Selalu usage terbaru: cache bisa lebih tua dari interval keep-alive.
+    let info = match check_billing(gh, opts, config, token, &owner, true) {
+        billing::QuotaStatus::Ok(info) | billing::QuotaStatus::Exhausted(info) => info,
+        billing::QuotaStatus::Unknown(reason) => {
+            warn!("Spend guard dilewati, billing tidak terbaca: {}", reason.lines().next().unwrap_or(""));
+            return None;
+        }
+    };
+    record_usage(opts, &config.billing.history_path, &owner.key(), &info);
+    // Core dan harga dari node yang benar-benar di-deploy (bisa sudah di-downgrade forecast).
+    let (cores, hourly) = match (state.cycle_cores, forecast::total_cores(&config.nodes)) {
+        (Some(cores), _) => (cores, state.cycle_hourly_usd),
+        (None, Some(cores)) => (cores, config.billing.prices.nodes_hourly(&config.nodes)),
+        (None, None) => {
+            info!("Spend guard dilewati: machine type tidak dikenal, isi `cores` di node.");
+            return None;
+        }
+    };
+    guard::check(&config.billing, cores, hourly, remaining, &info).err()
+}
+
+/// Stop semua node lewat `policy`. Return node yang gagal di-stop; yang sudah
+/// hilang dianggap beres.
+fn stop_nodes(gh: &dyn GhBackend, token: &str, deployed: &BTreeMap<String, String>, policy: &retry::RetryPolicy) -> Vec<String> {
+    let mut failed = Vec::new();
+    for (node, codespace) in deployed {
+        let _node = log::node(node);
+        info!("Stopping {}...", codespace);
+        match policy.run("Stop", github::GHError::is_retryable, |_| gh.stop_codespace(token, codespace)) {
+            Ok(()) => info!("Stopped"),
+            Err(github::GHError::NotFound(_)) => info!("Already gone"),
+            Err(e) => {
+                error!("Gagal stop {}: {}", codespace, e);
+                failed.push(format!("{} ({})", node, codespace));
+            }
+        }
+    }
+    failed
+}
+
+/// Simpan snapshot usage hari ini ke history. Dry-run tidak menulis file.
+fn record_usage(opts: &GlobalOpts, path: &str, key: &str, info: &billing::BillingInfo) -> history::History {
+    let mut history = history::load_history(path);
+    history.record(key, Utc::now().date_naive(), info);
+    if !opts.dry_run {
+        if let Err(e) = history::save_history(path, &history) {
+            warn!("Gagal menyimpan {}: {}", path, e);
+        }
+    }
+    history
+}
+
+/// Simpan state, atau hanya print apa yang akan disimpan saat dry-run.
+fn persist_state(opts: &GlobalOpts, state: &config::State) {
+    if opts.dry_run {
+        info!("[dry-run] Would save {} (token index {})", opts.state_path, state.current_account_index);
+    } else if let Err(e) = config::save_state(&opts.state_path, state) {
+        warn!("Gagal menyimpan {}: {}", opts.state_path, e);
+    }
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    
-    if args.len() > 1 && args[1] == "status" {
-        show_status();
-        return;
+    let args: Vec<String> = env::args().skip(1).collect();
+    let cli = match cli::parse(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            eprintln!("Jalankan dengan --help untuk daftar command.");
+            process::exit(cli::exit::USAGE);
+        }
+    };
+    let opts = &cli.global;
+    let max_bytes = (opts.log_max_mb * 1024.0 * 1024.0) as u64;
+    if let Err(e) = log::init(opts.log_level, opts.log_format, opts.log_file.as_deref(), max_bytes) {
+        eprintln!("FATAL: --log-file {}: {}", opts.log_file.as_deref().unwrap_or(""), e);
+        process::exit(cli::exit::CONFIG);
     }
-    
-    if args.len() > 1 && args[1] == "verify" {
-        verify_current();
+
+    if let Command::Help = cli.command {
+        println!("{}", cli::USAGE);
         return;
     }
-    
-    if args.len() < 2 {
-        eprintln!("Error: Nama repo belum dikasih!");
-        eprintln!("Usage: cargo run -- username/nama-repo");
-        return;
+    if let Command::Status = cli.command {
+        process::exit(show_status(opts));
     }
-    
-    let repo_name = &args[1];
 
-    println!("==================================================");
-    println!("   FULL AUTO ORCHESTRATOR (NUKE & CREATE MODE)");
-    println!("==================================================");
+    // Config yang rusak dilaporkan oleh command-nya sendiri; di sini cukup timeouts.
+    let timeouts = config::load_config(&opts.config_path).map(|c| c.timeouts).unwrap_or_default();
+    let gh = match backend::from_env(&timeouts) {
+        Ok(b) if opts.dry_run => Box::new(backend::DryRunBackend::new(b)),
+        Ok(b) => b,
+        Err(e) => {
+            error!("FATAL: {}", e);
+            process::exit(cli::exit::CONFIG);
+        }
+    };
+    let gh = gh.as_ref();
+
+    let code = match &cli.command {
+        Command::Run { repo, timing } => run(gh, opts, repo, timing),
+        Command::Verify => verify_current(gh, opts),
+        Command::Billing { json, repo } => show_billing(gh, opts, *json, repo.as_deref()),
+        Command::Cleanup { repo, all } => cleanup(gh, opts, repo, *all),
+        Command::Exec { node, command } => exec_nodes(gh, opts, node.as_deref(), command),
+        Command::Help | Command::Status => unreachable!(),
+    };
+    process::exit(code);
+}
+
+/// Full auto loop: deploy, keep-alive, lalu rotasi token. Hanya return
+/// saat error fatal atau setelah satu cycle dry-run.
+fn run(gh: &dyn GhBackend, opts: &GlobalOpts, repo_name: &str, timing_flags: &cli::TimingFlags) -> i32 {
+    info!("FULL AUTO ORCHESTRATOR");
+    if opts.dry_run {
+        info!("DRY RUN: perintah yang mengubah sesuatu hanya di-print");
+    }
     
-    println!("\nLoading tokens.json...");
-    let config = match config::load_config("tokens.json") {
+    info!("Loading {}...", opts.config_path);
+    let mut config = match config::load_config(&opts.config_path) {
         Ok(cfg) => cfg,
         Err(e) => {
-            eprintln!("FATAL: {}", e);
-            return;
+            error!("FATAL: {}", e);
+            return cli::exit::CONFIG;
         }
     };
+    timing_flags.apply(&mut config.timing);
+    if let Err(e) = config.timing.validate(&config.nodes) {
+        error!("FATAL: {}", e);
+        return cli::exit::USAGE;
+    }
+    let timing = &config.timing;
     
-    println!("Loaded {} tokens", config.tokens.len());
-    println!("Target Repo: {}", repo_name);
+    info!("Loaded {} tokens, {} node(s)", config.tokens.len(), config.nodes.len());
+    info!("Target Repo: {}", repo_name);
+    info!("Strategy: {}", match config.strategy {
+        config::Strategy::Nuke => "nuke & create",
+        config::Strategy::Reconcile => "reconcile",
+    });
+    info!("Cycle: {}h per token, keep-alive every {}h", timing.run_duration_hours, timing.keep_alive_interval_hours);
+    if config.cleanup_all {
+        warn!("cleanup_all aktif, SEMUA codespace di repo akan dihapus");
+    }
+
+    let state = match config::load_state(&opts.state_path) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("FATAL: {}", e);
+            return cli::exit::CONFIG;
+        }
+    };
+    if state.current_account_index >= config.tokens.len() {
+        error!("FATAL: Invalid token index {} di {}, hanya ada {} token", state.current_account_index, opts.state_path, config.tokens.len());
+        return cli::exit::CONFIG;
+    }
 
-    let mut state = config::load_state(STATE_FILE).unwrap_or_default();
-    let mut i = state.current_account_index;
+    let save = |state: &config::State| persist_state(opts, state);
+    Orchestrator::new(gh, opts, &config, repo_name, &clock::SystemClock, &save, state).run()
+}
+
+/// Hasil satu percobaan deploy di token aktif.
+enum Attempt {
+    /// Node jalan, cycle dimulai.
+    Deployed(BTreeMap<String, String>),
+    /// Token ini tidak dipakai, sudah pindah ke token berikutnya.
+    Rotated,
+    /// Coba lagi token yang sama, jedanya sudah ditunggu.
+    Retry,
+    /// Berhenti dengan exit code ini.
+    Exit(i32),
+}
 
-    if state.current_account_index > 0 {
-        println!("Continuing from token index: {}", i);
+/// Main loop `run`. Backend, jam dan cara menyimpan state diberikan dari
+/// luar, jadi setiap langkah bisa dites dengan backend palsu tanpa menunggu.
+struct Orchestrator<'a> {
+    gh: &'a dyn GhBackend,
+    opts: &'a GlobalOpts,
+    config: &'a config::Config,
+    repo: &'a str,
+    clock: &'a dyn clock::Clock,
+    save: &'a dyn Fn(&config::State),
+    state: config::State,
+    /// Token pertama yang dicoba, dry-run berhenti setelah satu putaran.
+    first_index: usize,
+    deploy_retry: retry::RetryPolicy,
+    deploy_failures: u32,
+    billing_checks: u32,
+}
+
+impl<'a> Orchestrator<'a> {
+    fn new(gh: &'a dyn GhBackend, opts: &'a GlobalOpts, config: &'a config::Config, repo: &'a str, clock: &'a dyn clock::Clock, save: &'a dyn Fn(&config::State), mut state: config::State) -> Self {
+        // Restart setelah deadline: cycle lama sudah selesai, jangan deploy ulang di token yang sama.
+        if let Some(deadline) = state.cycle_deadline.filter(|d| *d <= clock.now()) {
+            info!("Cycle token #{} sudah selesai ({} UTC), lanjut ke token berikutnya.", state.current_account_index + 1, deadline.format("%Y-%m-%d %H:%M"));
+            state.end_cycle(config.tokens.len());
+            save(&state);
+        }
+        if state.current_account_index > 0 {
+            info!("Continuing from token index: {}", state.current_account_index);
+        }
+        let mut deploy_retry = config.retry.deploy.clone();
+        if let Some(minutes) = config.timing.deploy_retry_minutes {
+            deploy_retry.initial_delay_secs = minutes * 60.0;
+        }
+        Orchestrator {
+            gh,
+            opts,
+            config,
+            repo,
+            clock,
+            save,
+            first_index: state.current_account_index,
+            state,
+            deploy_retry,
+            deploy_failures: 0,
+            billing_checks: 0,
+        }
     }
 
-    println!("\nStarting full auto loop...\n");
+    fn index(&self) -> usize {
+        self.state.current_account_index
+    }
 
-    loop {
-        let token = &config.tokens[i];
-        
-        println!("==================================================");
-        println!("Token #{} of {}", i + 1, config.tokens.len());
-        println!("==================================================");
-        
-        let username = match github::get_username(token) {
+    fn token(&self) -> &'a str {
+        &self.config.tokens[self.index()]
+    }
+
+    fn persist(&self) {
+        (self.save)(&self.state);
+    }
+
+    fn run(&mut self) -> i32 {
+        let mut resumed = self.resume();
+
+        info!("Starting full auto loop...");
+
+        loop {
+            log::set_account(Some(format!("#{}", self.index() + 1)));
+            info!("Token #{} of {}", self.index() + 1, self.config.tokens.len());
+
+            let deployed = match resumed.take() {
+                Some(deployed) => deployed,
+                None => match self.attempt() {
+                    Attempt::Deployed(deployed) => deployed,
+                    Attempt::Rotated | Attempt::Retry => continue,
+                    Attempt::Exit(code) => return code,
+                },
+            };
+
+            if self.opts.dry_run {
+                let run_hours = match (self.state.cycle_started_at, self.state.cycle_deadline) {
+                    (Some(started), Some(deadline)) => (deadline - started).num_minutes() as f64 / 60.0,
+                    _ => self.config.timing.run_duration_hours,
+                };
+                info!("[dry-run] Would run for {:.1} hours, keep-alive every {} hours:", run_hours, self.config.timing.keep_alive_interval_hours);
+                restart_nodes(self.gh, self.clock, self.token(), &self.config.nodes, &deployed);
+                self.state.current_account_index = (self.index() + 1) % self.config.tokens.len();
+                info!("[dry-run] Then switch to token #{}", self.index() + 1);
+                self.persist();
+                return cli::exit::OK;
+            }
+
+            info!("State saved");
+            self.cycle(&deployed);
+        }
+    }
+
+    /// Lanjutkan cycle yang tercatat di state kalau node-nya masih bisa dipakai.
+    fn resume(&self) -> Option<BTreeMap<String, String>> {
+        resume_cycle(self.gh, self.token(), &self.config.nodes, &self.state, &self.config.retry.startup, self.clock.now())
+    }
+
+    /// Pindah ke token berikutnya lalu tunggu `rotation_delay`. Dry-run
+    /// berhenti setelah semua token dicoba tanpa ada yang bisa deploy.
+    fn rotate(&mut self) -> Attempt {
+        self.state.current_account_index = (self.index() + 1) % self.config.tokens.len();
+        self.persist();
+        if self.opts.dry_run && self.index() == self.first_index {
+            info!("[dry-run] Semua token sudah dicoba, tidak ada yang bisa deploy.");
+            return Attempt::Exit(cli::exit::FAILURE);
+        }
+        self.clock.sleep(self.config.timing.rotation_delay());
+        Attempt::Rotated
+    }
+
+    /// Satu percobaan deploy dengan token aktif: cek token, billing dan
+    /// forecast, lalu deploy node.
+    fn attempt(&mut self) -> Attempt {
+        let (gh, opts, config, token) = (self.gh, self.opts, self.config, self.token());
+        let timing = &config.timing;
+        let username = match github::get_username(gh, token) {
             Ok(u) => {
-                println!("Valid token for: @{}", u);
+                log::set_account(Some(format!("#{} @{}", self.index() + 1, u)));
+                info!("Valid token for: @{}", u);
                 u
             }
             Err(github::GHError::AuthError(msg)) => {
-                eprintln!("Token INVALID: {}", msg.lines().next().unwrap_or(""));
-                eprintln!("Skip to next...\n");
-                i = (i + 1) % config.tokens.len();
-                state.current_account_index = i;
-                config::save_state(STATE_FILE, &state).ok();
-                thread::sleep(Duration::from_secs(3));
-                continue;
+                warn!("Token INVALID: {}", msg.lines().next().unwrap_or(""));
+                warn!("Skip to next...");
+                return self.rotate();
+            }
+            Err(e @ github::GHError::RateLimited { .. }) => {
+                let wait = e.rate_limit_wait().unwrap_or_default();
+                warn!("{}", e);
+                warn!("Retry in {:.1} min...", wait.as_secs_f32() / 60.0);
+                self.clock.sleep(wait);
+                return Attempt::Retry;
             }
             Err(e) => {
-                eprintln!("Error: {}", e);
-                i = (i + 1) % config.tokens.len();
-                state.current_account_index = i;
-                config::save_state(STATE_FILE, &state).ok();
-                thread::sleep(Duration::from_secs(3));
-                continue;
+                warn!("Error: {}", e);
+                return self.rotate();
             }
         };
 
-        println!("\nChecking billing quota...");
-        let billing = billing::get_billing_info(token, &username).unwrap();
-        billing::display_billing(&billing, &username);
+        info!("Checking billing quota...");
+        let owner = billing::detect_owner(gh, token, self.repo, &username, &config.billing);
+        if let Some(reason) = config.billing.unfunded(&owner) {
+            error!("FATAL: {}", reason);
+            return Attempt::Exit(cli::exit::CONFIG);
+        }
+        let status = check_billing(gh, opts, config, token, &owner, opts.refresh);
+        billing::display_billing(&status, &owner);
 
-        if !billing.is_quota_ok {
-            eprintln!("   Kuota tidak cukup. Beralih ke akun berikutnya...\n");
-            i = (i + 1) % config.tokens.len();
-            state.current_account_index = i;
-            config::save_state(STATE_FILE, &state).ok();
-            thread::sleep(Duration::from_secs(3));
-            continue;
+        if let billing::QuotaStatus::Ok(info) | billing::QuotaStatus::Exhausted(info) = &status {
+            record_usage(opts, &config.billing.history_path, &owner.key(), info);
         }
 
-        let (mawari_name, nexus_name) = match github::nuke_and_create(token, repo_name) {
+        let skip = match &status {
+            billing::QuotaStatus::Ok(_) => false,
+            billing::QuotaStatus::Exhausted(_) if config.billing.max_budget_usd.is_some() => {
+                info!("Kuota gratis habis, forecast memakai sisa max_budget_usd.");
+                false
+            }
+            billing::QuotaStatus::Exhausted(_) => {
+                warn!("Kuota tidak cukup. Beralih ke akun berikutnya...");
+                true
+            }
+            billing::QuotaStatus::Unknown(_) => match config.billing.on_unknown {
+                billing::UnknownPolicy::Skip => {
+                    warn!("Kuota tidak bisa diverifikasi. Beralih ke akun berikutnya...");
+                    true
+                }
+                billing::UnknownPolicy::Proceed if config.billing.max_budget_usd.is_some() => {
+                    warn!("Kuota tidak bisa diverifikasi dan max_budget_usd tidak bisa dijaga, on_unknown: proceed diabaikan. Beralih ke akun berikutnya...");
+                    true
+                }
+                billing::UnknownPolicy::Proceed => {
+                    warn!("Kuota tidak bisa diverifikasi, tetap lanjut (on_unknown: proceed).");
+                    false
+                }
+                billing::UnknownPolicy::RetryLater if config.billing.unknown_retry.allows(self.billing_checks + 1) => {
+                    self.billing_checks += 1;
+                    let delay = config.billing.unknown_retry.delay(self.billing_checks);
+                    warn!("Cek ulang billing dalam {:.1} min...", delay.as_secs_f32() / 60.0);
+                    self.clock.sleep(delay);
+                    return Attempt::Retry;
+                }
+                billing::UnknownPolicy::RetryLater => {
+                    warn!("Billing tetap tidak terbaca. Beralih ke akun berikutnya...");
+                    true
+                }
+            },
+        };
+        self.billing_checks = 0;
+
+        if skip {
+            return self.rotate();
+        }
+
+        let (nodes, run_duration) = match &status {
+            billing::QuotaStatus::Ok(info) | billing::QuotaStatus::Exhausted(info) => {
+                match forecast::plan(&config.nodes, timing.run_duration(), info, &config.billing) {
+                    forecast::Decision::Deploy { nodes, run } => (nodes, run),
+                    forecast::Decision::Refuse(reason) => {
+                        warn!("Deploy ditolak: {}", reason);
+                        warn!("Beralih ke akun berikutnya...");
+                        return self.rotate();
+                    }
+                }
+            }
+            _ => (config.nodes.clone(), timing.run_duration()),
+        };
+
+        let mut ownership = github::Ownership {
+            owned: &mut self.state.owned_codespaces,
+            all: config.cleanup_all,
+        };
+        let deployment = match config.strategy {
+            config::Strategy::Nuke => github::nuke_and_create(gh, token, self.repo, &nodes, &mut ownership, &config.retry),
+            config::Strategy::Reconcile => reconcile::reconcile(gh, token, self.repo, &nodes, &self.state.nodes, &mut ownership, &config.retry),
+        };
+        let deployed = match deployment {
             Ok(names) => names,
+            Err(e) if opts.dry_run => {
+                warn!("[dry-run] Deployment would fail: {}", e);
+                return Attempt::Exit(cli::exit::FAILURE);
+            }
             Err(e) => {
-                eprintln!("Deployment failed: {}", e);
-                eprintln!("Retry in 5 min...\n");
-                thread::sleep(Duration::from_secs(5 * 60));
-                continue;
+                self.persist();
+                error!("Deployment failed: {}", e);
+                if let Some(wait) = e.rate_limit_wait() {
+                    warn!("Retry in {:.1} min...", wait.as_secs_f32() / 60.0);
+                    self.clock.sleep(wait);
+                    return Attempt::Retry;
+                }
+                if e.is_account_error() || !self.deploy_retry.allows(self.deploy_failures + 1) {
+                    warn!("Beralih ke akun berikutnya...");
+                    self.deploy_failures = 0;
+                    return self.rotate();
+                }
+                self.deploy_failures += 1;
+                let delay = self.deploy_retry.delay(self.deploy_failures);
+                warn!("Retry in {:.1} min...", delay.as_secs_f32() / 60.0);
+                self.clock.sleep(delay);
+                return Attempt::Retry;
             }
         };
 
-        println!("\n==================================================");
-        println!("         DEPLOYMENT SUCCESS");
-        println!("==================================================");
-        println!("Account  : @{}", username);
-        println!("Mawari   : {}", mawari_name);
-        println!("Nexus    : {}", nexus_name);
-        
-        state.current_account_index = i;
-        state.current_mawari_name = mawari_name.clone();
-        state.current_nexus_name = nexus_name.clone();
-        config::save_state(STATE_FILE, &state).ok();
-        
-        println!("State saved");
-        
-        let run_duration_hours = 20.0;
-        let run_duration = Duration::from_secs((run_duration_hours * 3600.0) as u64);
-        
-        println!("\nRunning for {:.1} hours", run_duration_hours);
-        println!("Keep-alive every 3.5 hours");
+        info!("DEPLOYMENT SUCCESS: @{}", username);
+        for (node, codespace) in &deployed {
+            let _node = log::node(node);
+            info!("Codespace {}", codespace);
+        }
+
+        let now = self.clock.now();
+        self.state.nodes = deployed.clone();
+        self.state.cycle_started_at = Some(now);
+        self.state.cycle_deadline = Some(after(now, run_duration));
+        self.state.last_keep_alive_at = None;
+        self.state.cycle_hourly_usd = config.billing.prices.nodes_hourly(&nodes);
+        self.state.cycle_cores = forecast::total_cores(&nodes);
+        self.state.guard_paused_at = None;
+        self.persist();
+        Attempt::Deployed(deployed)
+    }
+
+    /// Keep-alive sampai deadline cycle, lalu pindah ke token berikutnya.
+    fn cycle(&mut self, deployed: &BTreeMap<String, String>) {
+        let (gh, config, token) = (self.gh, self.config, self.token());
+        let timing = &config.timing;
+        let started = self.state.cycle_started_at.unwrap_or_else(|| self.clock.now());
+        let deadline = self.state.cycle_deadline.unwrap_or_else(|| after(started, timing.run_duration()));
+        info!("Running until {} UTC", deadline.format("%Y-%m-%d %H:%M"));
+        info!("Keep-alive every {} hours", timing.keep_alive_interval_hours);
         
-        println!("\nStarting keep-alive loop...\n");
+        info!("Starting keep-alive loop...");
         
-        let start_time = Instant::now();
         let mut cycle = 1;
         
-        while start_time.elapsed() < run_duration {
-            let remaining_duration = run_duration.saturating_sub(start_time.elapsed());
-            let sleep_duration = std::cmp::min(KEEP_ALIVE_INTERVAL, remaining_duration);
-
-            if sleep_duration.as_secs() > 60 {
-                 println!("\nNext keep-alive in {:.1}h...\n", sleep_duration.as_secs() as f32 / 3600.0);
-                 thread::sleep(sleep_duration);
-            } else {
-                 break;
+        loop {
+            let now = self.clock.now();
+            if (deadline - now).num_seconds() <= 60 {
+                break;
+            }
+            if let Some(paused) = self.state.guard_paused_at {
+                info!("Spend guard aktif sejak {} UTC, node di-stop. Pause sampai {} UTC.",
+                    paused.format("%Y-%m-%d %H:%M"), deadline.format("%Y-%m-%d %H:%M"));
+                self.clock.sleep((deadline - now).to_std().unwrap_or_default());
+                break;
+            }
+            let last_keep_alive = self.state.last_keep_alive_at.unwrap_or(started);
+            let next_keep_alive = std::cmp::min(after(last_keep_alive, timing.keep_alive_interval()), deadline);
+            if let Ok(sleep_duration) = (next_keep_alive - now).to_std() {
+                info!("Next keep-alive in {:.1}h...", sleep_duration.as_secs() as f32 / 3600.0);
+                self.clock.sleep(sleep_duration);
             }
 
-            if start_time.elapsed() >= run_duration {
+            let now = self.clock.now();
+            if now >= deadline {
                 break;
             }
             
-            let elapsed_hours = start_time.elapsed().as_secs() / 3600;
-            let remaining_hours = (run_duration.as_secs() - start_time.elapsed().as_secs()) / 3600;
+            let elapsed_hours = (now - started).num_hours();
+            let remaining_hours = (deadline - now).num_hours();
             
-            println!("--------------------------------------------------");
-            println!("Keep-Alive Cycle #{} | Elapsed: ~{}h | Remaining: ~{}h", 
+            info!("Keep-Alive Cycle #{} | Elapsed: ~{}h | Remaining: ~{}h", 
                 cycle, elapsed_hours, remaining_hours);
-            println!("--------------------------------------------------");
+            if let Some(hourly) = self.state.cycle_hourly_usd {
+                let hours = (now - started).num_minutes() as f64 / 60.0;
+                info!("Estimasi biaya cycle sejauh ini: ~${:.2} (${:.2}/h, list price)", hourly * hours, hourly);
+            }
             
-            restart_nodes(token, &mawari_name, &nexus_name);
+            if config.billing.guard.enabled {
+                if let Some(reason) = spend_guard(gh, self.opts, config, token, self.repo, &self.state, (deadline - now).to_std().unwrap_or_default()) {
+                    warn!("SPEND GUARD: {}", reason);
+                    let failed = stop_nodes(gh, token, deployed, &config.retry.delete);
+                    if failed.is_empty() {
+                        warn!("Node di-stop (tidak dihapus), loop di-pause sampai akhir cycle.");
+                        self.state.guard_paused_at = Some(self.clock.now());
+                    } else {
+                        error!("SPEND GUARD: gagal stop {}, node masih jalan dan ditagih. Dicoba lagi di keep-alive berikutnya.", failed.join(", "));
+                        self.state.last_keep_alive_at = Some(self.clock.now());
+                    }
+                    self.persist();
+                    continue;
+                }
+            }
+
+            restart_nodes(gh, self.clock, token, &config.nodes, deployed);
+            self.state.last_keep_alive_at = Some(self.clock.now());
+            self.persist();
             
             cycle += 1;
         }
         
-        println!("\n==================================================");
-        println!("Cycle complete! Used {:.1}h", run_duration_hours);
-        println!("Switching to next token...");
-        println!("==================================================\n");
+        let hours = (deadline - started).num_minutes() as f64 / 60.0;
+        info!("Cycle complete! Used {:.1}h", hours);
+        if let Some(hourly) = self.state.cycle_hourly_usd {
+            info!("Estimasi biaya cycle: ~${:.2} (list price)", hourly * hours);
+        }
+        info!("Switching to next token...");
         
-        i = (i + 1) % config.tokens.len();
-        state.current_account_index = i;
-        config::save_state(STATE_FILE, &state).ok();
+        self.state.end_cycle(config.tokens.len());
+        self.persist();
         
-        if i == 0 {
-            println!("Full rotation complete. Back to first token.\n");
+        if self.index() == 0 {
+            info!("Full rotation complete. Back to first token.");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::FakeBackend;
+    use crate::clock::{tests::FakeClock, Clock};
+    use crate::github::tests::{instant, instant_retry, nodes, REPO, TOKEN};
+
+    /// Deploy node lalu catat sebagai cycle yang masih berjalan.
+    fn running_cycle(gh: &FakeBackend) -> config::State {
+        let mut state = config::State::default();
+        let mut ownership = github::Ownership { owned: &mut state.owned_codespaces, all: false };
+        state.nodes = github::nuke_and_create(gh, TOKEN, REPO, &nodes(), &mut ownership, &instant_retry()).unwrap();
+        state.cycle_started_at = Some(Utc::now());
+        state.cycle_deadline = Some(Utc::now() + chrono::Duration::hours(5));
+        state
+    }
+
+    #[test]
+    fn resume_keeps_node_that_is_still_starting() {
+        let gh = FakeBackend::default();
+        let state = running_cycle(&gh);
+        gh.set_state(&state.nodes["mawari"], "Starting");
+
+        assert_eq!(resume_cycle(&gh, TOKEN, &nodes(), &state, &instant(2), Utc::now()), Some(state.nodes.clone()));
+        assert!(gh.view_codespace(TOKEN, &state.nodes["mawari"]).is_ok());
+    }
+
+    #[test]
+    fn resume_starts_stopped_node() {
+        let gh = FakeBackend::default();
+        let state = running_cycle(&gh);
+        gh.stop_codespace(TOKEN, &state.nodes["nexus"]).unwrap();
+
+        assert_eq!(resume_cycle(&gh, TOKEN, &nodes(), &state, &instant(2), Utc::now()), Some(state.nodes.clone()));
+        assert!(gh.view_codespace(TOKEN, &state.nodes["nexus"]).unwrap().is_available());
+    }
+
+    #[test]
+    fn resume_redeploys_missing_or_broken_node() {
+        let gh = FakeBackend::default();
+        let state = running_cycle(&gh);
+        gh.set_state(&state.nodes["mawari"], "Failed");
+        assert_eq!(resume_cycle(&gh, TOKEN, &nodes(), &state, &instant(2), Utc::now()), None);
+
+        let gh = FakeBackend::default();
+        let state = running_cycle(&gh);
+        gh.delete_codespace(TOKEN, &state.nodes["nexus"]).unwrap();
+        assert_eq!(resume_cycle(&gh, TOKEN, &nodes(), &state, &instant(2), Utc::now()), None);
+    }
+
+    #[test]
+    fn expired_cycle_is_not_resumed() {
+        let gh = FakeBackend::default();
+        let mut state = running_cycle(&gh);
+        state.cycle_deadline = Some(Utc::now() - chrono::Duration::minutes(1));
+        assert_eq!(resume_cycle(&gh, TOKEN, &nodes(), &state, &instant(2), Utc::now()), None);
+
+        state.current_account_index = 1;
+        state.end_cycle(2);
+        assert_eq!(state.current_account_index, 0);
+        assert!(state.cycle_deadline.is_none() && state.cycle_started_at.is_none());
+    }
+
+    /// Config dengan `tokens`, retry tanpa delay dan file billing di direktori
+    /// sementara per test.
+    fn test_config(name: &str, tokens: &[&str]) -> config::Config {
+        let dir = env::temp_dir().join(format!("orchestrator-{}-{}", name, process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config: config::Config = serde_json::from_value(serde_json::json!({ "tokens": tokens })).unwrap();
+        config.nodes = nodes();
+        config.retry = instant_retry();
+        config.billing.cache_path = dir.join("billing_cache.json").to_string_lossy().into_owned();
+        config.billing.history_path = dir.join("billing_history.json").to_string_lossy().into_owned();
+        config
+    }
+
+    #[test]
+    fn invalid_token_rotates_to_next() {
+        let gh = FakeBackend::default();
+        let (opts, clock) = (GlobalOpts::default(), FakeClock::new());
+        let config = test_config("invalid", &["ghp_invalid01", "ghp_good00002"]);
+        let saved = std::cell::RefCell::new(Vec::new());
+        let save = |s: &config::State| saved.borrow_mut().push(s.current_account_index);
+        let mut orchestrator = Orchestrator::new(&gh, &opts, &config, REPO, &clock, &save, config::State::default());
+
+        assert!(matches!(orchestrator.attempt(), Attempt::Rotated));
+        assert_eq!(orchestrator.index(), 1);
+        assert_eq!(saved.borrow().as_slice(), &[1]);
+
+        let Attempt::Deployed(deployed) = orchestrator.attempt() else { panic!("token #2 harus deploy") };
+        assert_eq!(deployed.len(), 2);
+        assert_eq!(orchestrator.state.nodes, deployed);
+        assert_eq!(orchestrator.state.cycle_started_at, Some(clock.now()));
+    }
+
+    #[test]
+    fn exhausted_account_is_skipped() {
+        let gh = FakeBackend::default();
+        let (opts, clock) = (GlobalOpts::default(), FakeClock::new());
+        let config = test_config("exhausted", &["ghp_exhausted1", "ghp_good00002"]);
+        let mut orchestrator = Orchestrator::new(&gh, &opts, &config, REPO, &clock, &|_| {}, config::State::default());
+
+        assert!(matches!(orchestrator.attempt(), Attempt::Rotated));
+        assert_eq!(orchestrator.index(), 1);
+        assert!(gh.list_codespaces("ghp_exhausted1", REPO).unwrap().is_empty());
+
+        assert!(matches!(orchestrator.attempt(), Attempt::Deployed(_)));
+    }
+
+    #[test]
+    fn resumed_cycle_runs_to_deadline_then_rotates() {
+        let gh = FakeBackend::default();
+        let (opts, clock) = (GlobalOpts::default(), FakeClock::new());
+        let config = test_config("resume", &["ghp_good00001", TOKEN]);
+        let mut state = running_cycle(&gh);
+        state.current_account_index = 1;
+        let deadline = state.cycle_deadline.unwrap();
+        let mut orchestrator = Orchestrator::new(&gh, &opts, &config, REPO, &clock, &|_| {}, state.clone());
+
+        let deployed = orchestrator.resume().expect("cycle masih berjalan");
+        assert_eq!(deployed, state.nodes);
+
+        orchestrator.cycle(&deployed);
+        assert!(clock.now() >= deadline - chrono::Duration::minutes(1));
+        assert_eq!(orchestrator.index(), 0);
+        assert!(orchestrator.state.cycle_deadline.is_none() && orchestrator.state.last_keep_alive_at.is_none());
+        let names: Vec<String> = gh.list_codespaces(TOKEN, REPO).unwrap().into_iter().map(|cs| cs.name).collect();
+        assert_eq!(names.len(), 2);
+        assert!(state.nodes.values().all(|name| names.contains(name)));
+    }
+
+    #[test]
+    fn restart_after_deadline_starts_on_next_token() {
+        let gh = FakeBackend::default();
+        let (opts, clock) = (GlobalOpts::default(), FakeClock::new());
+        let config = test_config("expired", &[TOKEN, "ghp_good00002"]);
+        let mut state = running_cycle(&gh);
+        state.cycle_deadline = Some(clock.now() - chrono::Duration::minutes(1));
+        let orchestrator = Orchestrator::new(&gh, &opts, &config, REPO, &clock, &|_| {}, state);
+
+        assert_eq!(orchestrator.index(), 1);
+        assert!(orchestrator.resume().is_none());
+    }
+}
@@ -0,0 +1,90 @@
+// src/pricing.rs
+
+use crate::billing::BillingInfo;
+use crate::config::NodeConfig;
+use crate::forecast;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Harga list Codespaces (USD) per jam untuk setiap jumlah core.
+const DEFAULT_COMPUTE_HOURLY: &[(u32, f64)] = &[
+    (2, 0.18),
+    (4, 0.36),
+    (8, 0.72),
+    (16, 1.44),
+    (32, 2.88),
+];
+
+/// Object `billing.prices` di config. Entry yang tidak diisi ikut default.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct Prices {
+    /// Key = jumlah core, value = USD per jam.
+    pub compute_hourly: BTreeMap<u32, f64>,
+    pub storage_gb_month: f64,
+}
+
+impl Default for Prices {
+    fn default() -> Self {
+        Prices {
+            compute_hourly: BTreeMap::new(),
+            storage_gb_month: 0.07,
+        }
+    }
+}
+
+impl Prices {
+    pub fn validate(&self) -> Result<(), String> {
+        let values = self.compute_hourly.values().chain(std::iter::once(&self.storage_gb_month));
+        if values.into_iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err("billing.prices harus angka >= 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// USD per jam untuk machine `cores` core. Ukuran di luar tabel dihitung
+    /// linear dari harga 2-core.
+    pub fn hourly(&self, cores: u32) -> f64 {
+        self.compute_hourly.get(&cores).copied()
+            .or_else(|| DEFAULT_COMPUTE_HOURLY.iter().find(|(c, _)| *c == cores).map(|(_, p)| *p))
+            .unwrap_or_else(|| self.per_core_hour() * cores as f64)
+    }
+
+    /// Harga satu core-hour, dipakai untuk usage yang machine-nya tidak diketahui.
+    pub fn per_core_hour(&self) -> f64 {
+        let two_core = self.compute_hourly.get(&2).copied().unwrap_or(DEFAULT_COMPUTE_HOURLY[0].1);
+        two_core / 2.0
+    }
+
+    pub fn node_hourly(&self, node: &NodeConfig) -> Option<f64> {
+        forecast::machine_cores(node).map(|cores| self.hourly(cores))
+    }
+
+    /// Total USD per jam semua node, `None` kalau ada machine yang tidak dikenal.
+    pub fn nodes_hourly(&self, nodes: &[NodeConfig]) -> Option<f64> {
+        nodes.iter().map(|node| self.node_hourly(node)).sum()
+    }
+
+    /// Estimasi yang sudah ditagih di periode ini, yaitu usage di atas kuota.
+    pub fn overage_cost(&self, info: &BillingInfo) -> f64 {
+        let compute = (info.total_core_hours_used - info.included_core_hours).max(0.0) as f64 * self.per_core_hour();
+        let storage = (info.storage_gb_months_used - info.included_storage_gb_months).max(0.0) as f64 * self.storage_gb_month;
+        compute + storage
+    }
+}
+
+/// Print estimasi biaya list price per node untuk `hours` jam.
+pub fn print_cycle_estimate(nodes: &[NodeConfig], hours: f64, prices: &Prices) {
+    info!("Estimasi biaya cycle (list price, sebelum kuota gratis):");
+    let mut total = 0.0;
+    for node in nodes {
+        match prices.node_hourly(node) {
+            Some(hourly) => {
+                total += hourly * hours;
+                info!("{:<12} {:<20} ${:.2}/h x {:.1}h = ${:.2}", node.name, node.machine, hourly, hours, hourly * hours);
+            }
+            None => info!("{:<12} {:<20} harga tidak diketahui", node.name, node.machine),
+        }
+    }
+    info!("Total ~${:.2}", total);
+}
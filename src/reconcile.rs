@@ -0,0 +1,207 @@
+// src/reconcile.rs
+
+use crate::backend::GhBackend;
+use crate::config::NodeConfig;
+use crate::github::{self, Codespace, GHError, Ownership};
+use crate::log;
+use crate::retry::{RetryConfig, RetryPolicy};
+use std::collections::BTreeMap;
+
+/// Apa yang akan dilakukan untuk satu node.
+pub enum NodeAction {
+    /// Sudah Available (atau sedang naik), dipakai apa adanya.
+    Keep(Codespace),
+    /// Stopped (atau sedang shutdown), cukup di-start.
+    Start(Codespace),
+    /// State rusak atau machine beda, hapus lalu buat ulang.
+    Recreate(Codespace),
+    /// Belum ada sama sekali.
+    Create,
+}
+
+pub struct PlanEntry<'a> {
+    pub node: &'a NodeConfig,
+    pub action: NodeAction,
+}
+
+pub fn classify(node: &NodeConfig, cs: Codespace) -> NodeAction {
+    if cs.machine != node.machine && !cs.machine.is_empty() {
+        return NodeAction::Recreate(cs);
+    }
+    match cs.state.as_str() {
+        "Available" | "Starting" | "Provisioning" | "Created" | "Queued" | "Rebuilding" | "Awaiting" | "Updating" => NodeAction::Keep(cs),
+        "Shutdown" | "ShuttingDown" => NodeAction::Start(cs),
+        _ => NodeAction::Recreate(cs),
+    }
+}
+
+/// Start codespace lewat `policy`. Selama masih ShuttingDown GitHub menolak
+/// dengan 409, jadi itu ikut dicoba ulang seperti error sementara.
+pub fn start_codespace(gh: &dyn GhBackend, token: &str, name: &str, policy: &RetryPolicy) -> Result<(), GHError> {
+    let retryable = |e: &GHError| e.is_retryable() || matches!(e, GHError::Http { status: 409, .. });
+    policy.run("Start", retryable, |_| gh.start_codespace(token, name))
+}
+
+/// Cocokkan node yang diinginkan dengan codespace yang ada. Nama yang
+/// tercatat di state diutamakan, kalau tidak ada pakai display name.
+pub fn plan<'a>(nodes: &'a [NodeConfig], existing: &[Codespace], recorded: &BTreeMap<String, String>) -> Vec<PlanEntry<'a>> {
+    let mut claimed: Vec<&str> = Vec::new();
+    nodes.iter().map(|node| {
+        let found = recorded.get(&node.name)
+            .and_then(|name| existing.iter().find(|cs| &cs.name == name))
+            .or_else(|| existing.iter().find(|cs| cs.display_name == node.display_name() && !claimed.contains(&cs.name.as_str())));
+        let action = match found {
+            Some(cs) => {
+                claimed.push(&cs.name);
+                classify(node, cs.clone())
+            }
+            None => NodeAction::Create,
+        };
+        PlanEntry { node, action }
+    }).collect()
+}
+
+pub fn print_plan(plan: &[PlanEntry]) {
+    info!("Reconcile plan:");
+    for entry in plan {
+        let (label, detail) = match &entry.action {
+            NodeAction::Keep(cs) => ("KEEP", format!("{} ({})", cs.name, cs.state)),
+            NodeAction::Start(cs) => ("START", format!("{} ({})", cs.name, cs.state)),
+            NodeAction::Recreate(cs) => ("RECREATE", format!("{} ({}, {})", cs.name, cs.state, cs.machine)),
+            NodeAction::Create => ("CREATE", format!("({})", entry.node.machine)),
+        };
+        info!("{:<12} {:<9} {}", entry.node.name, label, detail);
+    }
+}
+
+/// Samakan codespace di repo dengan daftar node tanpa menghapus yang sehat.
+/// Hanya codespace milik orchestrator yang bisa diadopsi; sisanya dibiarkan.
+pub fn reconcile(gh: &dyn GhBackend, token: &str, repo: &str, nodes: &[NodeConfig], recorded: &BTreeMap<String, String>, ownership: &mut Ownership, retry: &RetryConfig) -> Result<BTreeMap<String, String>, GHError> {
+    info!("Scanning existing codespaces for repo '{}'...", repo);
+    let existing: Vec<Codespace> = gh.list_codespaces(token, repo)?
+        .into_iter()
+        .filter(|cs| ownership.owns(cs))
+        .collect();
+    let plan = plan(nodes, &existing, recorded);
+    print_plan(&plan);
+
+    let mut deployed = BTreeMap::new();
+    for entry in &plan {
+        let node = entry.node;
+        let _node = log::node(&node.name);
+        let name = match &entry.action {
+            NodeAction::Keep(cs) => cs.name.clone(),
+            NodeAction::Start(cs) => {
+                info!("Starting {}...", cs.name);
+                start_codespace(gh, token, &cs.name, &retry.startup)?;
+                cs.name.clone()
+            }
+            NodeAction::Recreate(cs) => {
+                info!("Recreating {}...", node.name);
+                github::delete_codespace(gh, token, &cs.name, &retry.delete)?;
+                ownership.forget(&cs.name);
+                github::create_node(gh, token, repo, node, ownership)?
+            }
+            NodeAction::Create => {
+                info!("Creating {} ({})...", node.display_name(), node.machine);
+                github::create_node(gh, token, repo, node, ownership)?
+            }
+        };
+        deployed.insert(node.name.clone(), name);
+    }
+
+    info!("Starting nodes via direct script execution...");
+    for node in nodes {
+        let _node = log::node(&node.name);
+        github::wait_and_run_startup_script(gh, token, &deployed[&node.name], &node.startup_command, &retry.startup)?;
+    }
+    Ok(deployed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::FakeBackend;
+    use crate::github::tests::{instant_retry, node, nodes, REPO, TOKEN};
+
+    /// Deploy awal lewat reconcile di repo kosong, return (owned, deployed).
+    fn deploy(gh: &FakeBackend, nodes: &[NodeConfig]) -> (Vec<String>, BTreeMap<String, String>) {
+        let mut owned = Vec::new();
+        let mut ownership = Ownership { owned: &mut owned, all: false };
+        let deployed = reconcile(gh, TOKEN, REPO, nodes, &BTreeMap::new(), &mut ownership, &instant_retry()).unwrap();
+        (owned, deployed)
+    }
+
+    fn run(gh: &FakeBackend, nodes: &[NodeConfig], owned: &mut Vec<String>, recorded: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+        let mut ownership = Ownership { owned, all: false };
+        reconcile(gh, TOKEN, REPO, nodes, recorded, &mut ownership, &instant_retry()).unwrap()
+    }
+
+    #[test]
+    fn keeps_healthy_recorded_nodes() {
+        let gh = FakeBackend::default();
+        let (mut owned, first) = deploy(&gh, &nodes());
+        let second = run(&gh, &nodes(), &mut owned, &first);
+
+        assert_eq!(first, second);
+        assert_eq!(gh.list_codespaces(TOKEN, REPO).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn starts_stopped_node_instead_of_recreating() {
+        let gh = FakeBackend::default();
+        let (mut owned, first) = deploy(&gh, &nodes());
+        gh.stop_codespace(TOKEN, &first["mawari"]).unwrap();
+        let second = run(&gh, &nodes(), &mut owned, &first);
+
+        assert_eq!(first, second);
+        assert!(gh.view_codespace(TOKEN, &second["mawari"]).unwrap().is_available());
+    }
+
+    #[test]
+    fn waits_for_shutting_down_node_then_starts_it() {
+        let gh = FakeBackend::default();
+        let (mut owned, first) = deploy(&gh, &nodes());
+        gh.set_state(&first["nexus"], "ShuttingDown");
+        let second = run(&gh, &nodes(), &mut owned, &first);
+
+        assert_eq!(first, second);
+        assert!(gh.view_codespace(TOKEN, &second["nexus"]).unwrap().is_available());
+    }
+
+    #[test]
+    fn recreates_node_when_machine_changes() {
+        let gh = FakeBackend::default();
+        let (mut owned, first) = deploy(&gh, &nodes());
+        let changed = vec![node("mawari", "premiumLinux"), node("nexus", "standardLinux32gb")];
+        let second = run(&gh, &changed, &mut owned, &first);
+
+        assert_ne!(first["mawari"], second["mawari"]);
+        assert_eq!(first["nexus"], second["nexus"]);
+        assert!(gh.view_codespace(TOKEN, &first["mawari"]).is_err());
+        assert!(!owned.contains(&first["mawari"]));
+    }
+
+    #[test]
+    fn creates_missing_node() {
+        let gh = FakeBackend::default();
+        let (mut owned, first) = deploy(&gh, &nodes());
+        gh.delete_codespace(TOKEN, &first["nexus"]).unwrap();
+        let second = run(&gh, &nodes(), &mut owned, &first);
+
+        assert_eq!(first["mawari"], second["mawari"]);
+        assert_ne!(first["nexus"], second["nexus"]);
+        assert_eq!(gh.list_codespaces(TOKEN, REPO).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn does_not_adopt_foreign_codespace() {
+        let gh = FakeBackend::default();
+        let foreign = gh.create_codespace(TOKEN, REPO, &node("mawari", "basicLinux32gb")).unwrap();
+        let (owned, deployed) = deploy(&gh, &nodes());
+
+        assert_ne!(deployed["mawari"], foreign);
+        assert!(!owned.contains(&foreign));
+        assert!(gh.view_codespace(TOKEN, &foreign).is_ok());
+    }
+}
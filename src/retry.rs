@@ -0,0 +1,162 @@
+// src/retry.rs
+
+use crate::github::GHError;
+use serde::Deserialize;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Berapa kali dan seberapa lama menunggu sebelum operasi diulang.
+/// Delay ke-n = `initial_delay_secs * multiplier^(n-1)`, dibatasi
+/// `max_delay_secs`, lalu digeser acak +/- `jitter` (0.1 = 10%).
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total percobaan termasuk yang pertama. 0 = tanpa batas.
+    pub max_attempts: u32,
+    pub initial_delay_secs: f64,
+    pub max_delay_secs: f64,
+    pub multiplier: f64,
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay_secs: 5.0,
+            max_delay_secs: 60.0,
+            multiplier: 2.0,
+            jitter: 0.1,
+        }
+    }
+}
+
+/// Batas atas delay (satu hari), supaya konversi ke `Duration` tidak overflow.
+const MAX_DELAY_SECS: f64 = 24.0 * 3600.0;
+
+/// Policy per operasi, bisa diatur lewat object `retry` di config.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Hapus satu codespace.
+    pub delete: RetryPolicy,
+    /// Polling sampai codespace lama benar-benar hilang.
+    pub deletion_wait: RetryPolicy,
+    /// Tunggu SSH siap lalu jalankan startup command.
+    pub startup: RetryPolicy,
+    /// Deploy ulang di main loop. `timing.deploy_retry_minutes` kalau diisi menggantikan
+    /// delay awal; setelah `max_attempts` gagal berturut-turut pindah ke token berikutnya.
+    pub deploy: RetryPolicy,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            delete: RetryPolicy::default(),
+            deletion_wait: RetryPolicy {
+                max_attempts: 10,
+                initial_delay_secs: 5.0,
+                max_delay_secs: 15.0,
+                multiplier: 1.5,
+                jitter: 0.0,
+            },
+            startup: RetryPolicy {
+                max_attempts: 10,
+                initial_delay_secs: 15.0,
+                max_delay_secs: 60.0,
+                multiplier: 1.5,
+                jitter: 0.1,
+            },
+            deploy: RetryPolicy {
+                max_attempts: 0,
+                initial_delay_secs: 5.0 * 60.0,
+                max_delay_secs: 60.0 * 60.0,
+                multiplier: 2.0,
+                jitter: 0.1,
+            },
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let policies = [
+            ("delete", &self.delete),
+            ("deletion_wait", &self.deletion_wait),
+            ("startup", &self.startup),
+            ("deploy", &self.deploy),
+        ];
+        for (name, policy) in policies {
+            policy.validate().map_err(|e| format!("retry.{}: {}", name, e))?;
+        }
+        Ok(())
+    }
+}
+
+impl RetryPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [self.initial_delay_secs, self.max_delay_secs, self.multiplier, self.jitter];
+        if values.iter().any(|v| !v.is_finite() || *v < 0.0) {
+            return Err("delay, multiplier dan jitter harus angka >= 0".to_string());
+        }
+        if self.initial_delay_secs > MAX_DELAY_SECS || self.max_delay_secs > MAX_DELAY_SECS {
+            return Err(format!("delay maksimal {} detik", MAX_DELAY_SECS));
+        }
+        if self.multiplier < 1.0 {
+            return Err("multiplier harus >= 1".to_string());
+        }
+        if self.jitter > 1.0 {
+            return Err("jitter harus 0-1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Masih boleh mencoba lagi setelah `attempt` percobaan gagal?
+    pub fn allows(&self, attempt: u32) -> bool {
+        self.max_attempts == 0 || attempt < self.max_attempts
+    }
+
+    /// Jeda setelah operasi berhasil sebelum langkah berikutnya (mis. stop
+    /// sebelum delete), sama dengan delay awal tanpa jitter.
+    pub fn settle(&self) -> Duration {
+        Duration::from_secs_f64(self.initial_delay_secs)
+    }
+
+    /// Delay sebelum percobaan ke-`attempt + 1` (attempt mulai dari 1).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(64) as i32;
+        let base = (self.initial_delay_secs * self.multiplier.powi(exponent)).min(self.max_delay_secs);
+        // Cukup acak untuk menghindari retry serentak, tanpa dependency rand.
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        let spread = (nanos % 2001) as f64 / 1000.0 - 1.0;
+        Duration::from_secs_f64((base * (1.0 + self.jitter * spread)).max(0.0))
+    }
+
+    fn describe_limit(&self) -> String {
+        if self.max_attempts == 0 { "∞".to_string() } else { self.max_attempts.to_string() }
+    }
+
+    /// Jalankan `op` sampai sukses, error tidak retryable, atau percobaan habis.
+    /// Rate limit selalu ditunggu minimal sampai reset.
+    pub fn run<T>(
+        &self,
+        label: &str,
+        retryable: impl Fn(&GHError) -> bool,
+        mut op: impl FnMut(u32) -> Result<T, GHError>,
+    ) -> Result<T, GHError> {
+        let mut attempt = 1;
+        loop {
+            match op(attempt) {
+                Ok(value) => return Ok(value),
+                Err(e) if retryable(&e) && self.allows(attempt) => {
+                    let delay = self.delay(attempt).max(e.rate_limit_wait().unwrap_or_default());
+                    warn!("{} failed ({}/{}): {}", label, attempt, self.describe_limit(), e.to_string().lines().next().unwrap_or(""));
+                    warn!("Retrying in {:.0}s...", delay.as_secs_f64());
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}